@@ -0,0 +1,8 @@
+// Colors are written in sRGB, as picked in an image editor, and converted to
+// the linear values the render target expects. The target is an sRGB view or
+// a float format, so the hardware encodes them back on write.
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
@@ -0,0 +1,12 @@
+// Declarations shared by the vertex shaders and the fragment shaders that
+// may be paired with either of them.
+
+// Animation time in seconds, for custom fragment shaders.
+@group(0) @binding(0)
+var<uniform> time: f32;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+}
@@ -0,0 +1,70 @@
+use crate::{
+    curve::{renderer::CurveRenderer, Animation, Bezier},
+    scene::Scene,
+};
+
+pub struct RunConfig {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub curve: Option<Bezier>,
+    /// Played in a loop in place of the built-in animation, unless `curve`
+    /// is set.
+    pub animation: Option<Animation>,
+    /// Linear, like the colors the shaders output.
+    pub clear_color: wgpu::Color,
+    /// WGSL source defining `fs_main(in: VertexOutput) -> @location(0) vec4<f32>`
+    /// in place of the default white fill. It is appended to the declarations
+    /// both vertex shaders share, so `VertexOutput`, the `time` uniform and
+    /// `srgb_to_linear` are in scope.
+    pub fragment_shader: Option<String>,
+    /// Curves whose bounding box spans fewer pixels than this are skipped.
+    pub lod_threshold_px: f32,
+    /// MSAA samples per pixel: 1, 2, 4 or 8. Lowered to the largest count the
+    /// adapter supports.
+    pub sample_count: u32,
+    /// Prefers an `Rgba16Float` surface where available, so colors brighter
+    /// than 1.0 reach HDR displays instead of clipping.
+    pub hdr: bool,
+    /// Curves drawn in place of the animated one.
+    pub scene: Option<Scene>,
+    /// Drawn after the scene's curves, each with its own stroke width in
+    /// pixels and sRGB color.
+    pub curves: Vec<(Bezier, f64, [f32; 3])>,
+    /// Lights up every pixel a filled triangle touches, so hairlines don't
+    /// flicker. Ignored where the adapter doesn't support it.
+    pub conservative_rasterization: bool,
+    /// Smooths stroke edges in the fragment shader from the distance across
+    /// the stroke, without the cost of MSAA. Ignored with a custom
+    /// `fragment_shader`.
+    pub sdf_antialiasing: bool,
+    /// Added to the stroke renderers R cycles through, after the built-in ones.
+    pub renderers: Vec<Box<dyn CurveRenderer>>,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            title: String::from("Bezier"),
+            width: 800,
+            height: 600,
+            curve: None,
+            animation: None,
+            clear_color: wgpu::Color {
+                r: 0.1,
+                g: 0.2,
+                b: 0.3,
+                a: 1.0,
+            },
+            fragment_shader: None,
+            lod_threshold_px: 1.0,
+            sample_count: 4,
+            hdr: false,
+            scene: None,
+            curves: Vec::new(),
+            conservative_rasterization: false,
+            sdf_antialiasing: false,
+            renderers: Vec::new(),
+        }
+    }
+}
@@ -1,47 +1,1100 @@
+use cgmath::{BaseFloat, InnerSpace};
+
+use crate::{vertex::RenderData, Vertex};
+
+mod animation;
+mod curve3;
+#[cfg(feature = "font")]
+mod font;
+#[cfg(feature = "lyon")]
+mod lyon;
+mod rational;
 pub mod renderer;
+pub mod spline;
+
+pub use animation::{Animation, Easing};
+pub use curve3::{Bezier3, PolyLine3};
+pub use rational::RationalBezier;
+pub use spline::{Continuity, Spline};
+
+type Vector2<T = f64> = cgmath::Vector2<T>;
 
-type Vector2 = cgmath::Vector2<f64>;
+pub type Bezierf64 = Bezier<f64>;
 
 fn vec2(x: f64, y: f64) -> Vector2 {
     cgmath::vec2(x, y)
 }
 
-pub struct Bezier {
-    pub start: Vector2,
-    pub middle: Vector2,
-    pub end: Vector2,
+fn parameter<T: BaseFloat>(i: usize, count: usize) -> T {
+    T::from(i).unwrap() / T::from(count - 1).unwrap()
 }
 
-impl Bezier {
-    pub fn subdivide(&self, count: usize) -> PolyLine {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bezier<T: BaseFloat = f64> {
+    pub start: Vector2<T>,
+    pub middle: Vector2<T>,
+    pub end: Vector2<T>,
+}
+
+#[cfg(not(feature = "rayon"))]
+impl<T: BaseFloat> Bezier<T> {
+    pub fn subdivide(&self, count: usize) -> PolyLine<T> {
         PolyLine {
-            points: (0..count)
-                .map(|i| self.eval((i as f64) / (count - 1) as f64))
-                .collect(),
+            points: self.iter_subdivided(count).collect(),
         }
     }
 
-    pub fn new(start: Vector2, middle: Vector2, end: Vector2) -> Self {
+    pub fn subdivide_into(&self, count: usize, out: &mut Vec<Vector2<T>>) {
+        out.clear();
+        out.extend(self.iter_subdivided(count));
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: BaseFloat + Send + Sync> Bezier<T> {
+    pub fn subdivide(&self, count: usize) -> PolyLine<T> {
+        let mut points = Vec::with_capacity(count);
+        self.subdivide_into(count, &mut points);
+        PolyLine { points }
+    }
+
+    // Below this many points the thread pool costs more than it saves.
+    const PARALLEL_SUBDIVISION_COUNT: usize = 4096;
+
+    pub fn subdivide_into(&self, count: usize, out: &mut Vec<Vector2<T>>) {
+        use rayon::prelude::*;
+
+        if count < Self::PARALLEL_SUBDIVISION_COUNT {
+            out.clear();
+            out.extend(self.iter_subdivided(count));
+            return;
+        }
+        let poly = self.to_poly();
+        (0..count)
+            .into_par_iter()
+            .map(|i| poly.eval(parameter(i, count)))
+            .collect_into_vec(out);
+    }
+}
+
+impl<T: BaseFloat> Bezier<T> {
+    pub fn to_poly(&self) -> QuadraticPoly<T> {
+        let two = T::one() + T::one();
+        QuadraticPoly {
+            a: self.start - self.middle * two + self.end,
+            b: (self.middle - self.start) * two,
+            c: self.start,
+        }
+    }
+
+    pub fn new(start: Vector2<T>, middle: Vector2<T>, end: Vector2<T>) -> Self {
         Self { start, middle, end }
     }
 
-    fn eval(&self, t: f64) -> Vector2 {
+    /// The points `subdivide` would give, computed as they are consumed.
+    pub fn iter_subdivided(&self, count: usize) -> impl Iterator<Item = Vector2<T>> {
+        let poly = self.to_poly();
+        (0..count).map(move |i| poly.eval(parameter(i, count)))
+    }
+
+    pub fn de_casteljau_steps(&self, t: T) -> Vec<Vec<Vector2<T>>> {
+        let mut steps = vec![vec![self.start, self.middle, self.end]];
+        while let Some(last) = steps.last().filter(|level| level.len() > 1) {
+            let next = last
+                .windows(2)
+                .map(|pair| Self::lerp(pair[0], pair[1], t))
+                .collect();
+            steps.push(next);
+        }
+        steps
+    }
+
+    pub fn split(&self, t: T) -> (Self, Self) {
+        (self.trim(T::zero(), t), self.trim(t, T::one()))
+    }
+
+    pub fn trim(&self, t0: T, t1: T) -> Self {
+        Self::new(self.eval(t0), self.blossom(t0, t1), self.eval(t1))
+    }
+
+    fn blossom(&self, u: T, v: T) -> Vector2<T> {
+        let a = Self::lerp(self.start, self.middle, u);
+        let b = Self::lerp(self.middle, self.end, u);
+        Self::lerp(a, b, v)
+    }
+
+    pub fn split_n(&self, n: usize) -> Vec<Self> {
+        let bound = |i: usize| T::from(i).unwrap() / T::from(n).unwrap();
+        (0..n).map(|i| self.trim(bound(i), bound(i + 1))).collect()
+    }
+
+    pub fn eval(&self, t: T) -> Vector2<T> {
         let a = Self::lerp(self.start, self.middle, t);
         let b = Self::lerp(self.middle, self.end, t);
         Self::lerp(a, b, t)
     }
 
-    fn lerp(start: Vector2, end: Vector2, t: f64) -> Vector2 {
-        end * t + start * (1.0 - t)
+    pub fn derivative(&self, t: T) -> Vector2<T> {
+        let two = T::one() + T::one();
+        Self::lerp(self.middle - self.start, self.end - self.middle, t) * two
+    }
+
+    pub fn lerp(start: Vector2<T>, end: Vector2<T>, t: T) -> Vector2<T> {
+        end * t + start * (T::one() - t)
+    }
+
+    /// Builds the cubic through `p0` and `p1` with derivatives `m0` and `m1`.
+    pub fn from_hermite(
+        p0: Vector2<T>,
+        m0: Vector2<T>,
+        p1: Vector2<T>,
+        m1: Vector2<T>,
+    ) -> CubicBezier<T> {
+        let three = T::from(3).unwrap();
+        CubicBezier::new(p0, p0 + m0 / three, p1 - m1 / three, p1)
+    }
+
+    /// Compares control points, allowing each to be off by up to `eps`.
+    pub fn approx_eq(&self, other: &Self, eps: T) -> bool {
+        [
+            (self.start, other.start),
+            (self.middle, other.middle),
+            (self.end, other.end),
+        ]
+        .into_iter()
+        .all(|(a, b)| (a - b).magnitude() <= eps)
+    }
+
+    /// The same curve, reversed if needed so that it starts at the end that
+    /// comes first ordering by x, then y. A curve and its reverse share this
+    /// form, with `t` on one matching `1 - t` on the other. Ends that differ
+    /// only by noise may still order either way.
+    pub fn canonical(&self) -> Self {
+        if (self.end.x, self.end.y) < (self.start.x, self.start.y) {
+            Self::new(self.end, self.middle, self.start)
+        } else {
+            *self
+        }
     }
 }
 
-pub struct PolyLine {
-    pub points: Vec<Vector2>,
+impl Bezier {
+    pub fn convex_hull(&self) -> Vec<Vector2> {
+        convex_hull(&[self.start, self.middle, self.end])
+    }
+
+    /// The curve as SVG path data, in its own coordinates.
+    pub fn to_svg_path(&self) -> String {
+        format!(
+            "M {} {} Q {} {} {} {}",
+            self.start.x, self.start.y, self.middle.x, self.middle.y, self.end.x, self.end.y
+        )
+    }
+
+    /// A conservative circle around the control points, not the minimal one.
+    pub fn bounding_circle(&self) -> (Vector2, f64) {
+        let center = (self.start + self.middle + self.end) / 3.0;
+        let radius = [self.start, self.middle, self.end]
+            .iter()
+            .map(|point| (point - center).magnitude())
+            .fold(0.0, f64::max);
+        (center, radius)
+    }
+
+    /// The tight axis-aligned bounds as `(min, max)`.
+    pub fn bounding_box(&self) -> (Vector2, Vector2) {
+        let (x, y) = (
+            self.extent_along(vec2(1.0, 0.0)),
+            self.extent_along(vec2(0.0, 1.0)),
+        );
+        (vec2(x.0, y.0), vec2(x.1, y.1))
+    }
+
+    /// The curve centered and uniformly scaled to fit `[-1, 1]²`, along with
+    /// the homogeneous transform that maps it back.
+    pub fn normalized(&self) -> (Bezier, cgmath::Matrix3<f64>) {
+        let (min, max) = self.bounding_box();
+        let center = (min + max) / 2.0;
+        let extent = (max.x - min.x).max(max.y - min.y);
+        let scale = if extent > 0.0 { 2.0 / extent } else { 1.0 };
+        let normalize = |point: Vector2| (point - center) * scale;
+        let inverse =
+            cgmath::Matrix3::from_translation(center) * cgmath::Matrix3::from_scale(1.0 / scale);
+        (
+            Bezier::new(
+                normalize(self.start),
+                normalize(self.middle),
+                normalize(self.end),
+            ),
+            inverse,
+        )
+    }
+
+    /// Returns the center and the two half-axes of a box aligned to the chord.
+    pub fn obb(&self) -> (Vector2, Vector2, Vector2) {
+        let chord = self.end - self.start;
+        let axis = if chord.magnitude2() > 0.0 {
+            chord.normalize()
+        } else {
+            vec2(1.0, 0.0)
+        };
+        let normal = vec2(-axis.y, axis.x);
+        let (along, across) = (self.extent_along(axis), self.extent_along(normal));
+        let center = axis * (along.0 + along.1) / 2.0 + normal * (across.0 + across.1) / 2.0;
+        (
+            center,
+            axis * (along.1 - along.0) / 2.0,
+            normal * (across.1 - across.0) / 2.0,
+        )
+    }
+
+    fn extent_along(&self, direction: Vector2) -> (f64, f64) {
+        let incoming = (self.middle - self.start).dot(direction);
+        let outgoing = (self.end - self.middle).dot(direction);
+        let mut ts = vec![0.0, 1.0];
+        if incoming != outgoing {
+            let t = incoming / (incoming - outgoing);
+            if (0.0..=1.0).contains(&t) {
+                ts.push(t);
+            }
+        }
+        ts.into_iter().map(|t| self.eval(t).dot(direction)).fold(
+            (f64::INFINITY, f64::NEG_INFINITY),
+            |(min, max), projection| (min.min(projection), max.max(projection)),
+        )
+    }
+
+    pub fn frame_at(&self, t: f64) -> (Vector2, Vector2, Vector2) {
+        let derivative = self.derivative(t);
+        let direction = if derivative.magnitude2() > 0.0 {
+            derivative
+        } else {
+            self.end - self.start
+        };
+        let tangent = if direction.magnitude2() > 0.0 {
+            direction.normalize()
+        } else {
+            vec2(1.0, 0.0)
+        };
+        (self.eval(t), tangent, vec2(-tangent.y, tangent.x))
+    }
+
+    const OUTLINE_SAMPLES: usize = 32;
+    const OUTLINE_CAP_SEGMENTS: usize = 8;
+
+    /// A closed contour around a stroke of full width `width` with round
+    /// caps: the left offset forwards, the end cap, the right offset
+    /// backwards and the start cap.
+    pub fn outline(&self, width: f64) -> Vec<Vector2> {
+        let half = width / 2.0;
+        let frames: Vec<_> = (0..Self::OUTLINE_SAMPLES)
+            .map(|i| self.frame_at(parameter(i, Self::OUTLINE_SAMPLES)))
+            .collect();
+        let cap = |(center, tangent, normal): (Vector2, Vector2, Vector2)| {
+            (1..Self::OUTLINE_CAP_SEGMENTS).map(move |i| {
+                let angle = std::f64::consts::PI * i as f64 / Self::OUTLINE_CAP_SEGMENTS as f64;
+                center + (normal * angle.cos() + tangent * angle.sin()) * half
+            })
+        };
+
+        let mut points: Vec<Vector2> = frames
+            .iter()
+            .map(|&(point, _, normal)| point + normal * half)
+            .collect();
+        points.extend(cap(frames[frames.len() - 1]));
+        points.extend(
+            frames
+                .iter()
+                .rev()
+                .map(|&(point, _, normal)| point - normal * half),
+        );
+        let (start, tangent, normal) = frames[0];
+        points.extend(cap((start, -tangent, -normal)));
+        points
+    }
+
+    const Y_AT_X_ITERATIONS: usize = 64;
+
+    pub fn y_at_x(&self, x: f64) -> f64 {
+        debug_assert!(
+            (self.middle.x - self.start.x) * (self.end.x - self.middle.x) >= 0.0,
+            "y_at_x requires a curve monotonic in x"
+        );
+        let increasing = self.end.x >= self.start.x;
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..Self::Y_AT_X_ITERATIONS {
+            let middle = (low + high) / 2.0;
+            if (self.eval(middle).x < x) == increasing {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        self.eval((low + high) / 2.0).y
+    }
+
+    /// The number of evenly spaced points for `subdivide` to keep the curve
+    /// within `tolerance` of its polyline, from the bound |a| h^2 / 4 on the
+    /// error of a chord spanning a parameter interval of h.
+    pub fn subdivision_count(&self, tolerance: f64) -> usize {
+        let curvature = self.to_poly().a.magnitude();
+        ((curvature / (4.0 * tolerance)).sqrt().ceil() as usize)
+            .saturating_add(1)
+            .max(2)
+    }
+
+    const ARC_LENGTH_LUT_SIZE: usize = 256;
+
+    /// `count` points spaced evenly along the curve, located by interpolating
+    /// the parameter in a table of cumulative lengths.
+    pub fn subdivide_by_arclength(&self, count: usize) -> PolyLine {
+        let lengths = self
+            .subdivide(Self::ARC_LENGTH_LUT_SIZE)
+            .cumulative_lengths();
+        let total = lengths[lengths.len() - 1];
+        (0..count)
+            .map(|i| {
+                let distance = total * parameter::<f64>(i, count.max(2));
+                let j = lengths
+                    .partition_point(|&length| length < distance)
+                    .clamp(1, lengths.len() - 1);
+                let span = lengths[j] - lengths[j - 1];
+                let local = if span > 0.0 {
+                    (distance - lengths[j - 1]) / span
+                } else {
+                    0.0
+                };
+                let step = 1.0 / (Self::ARC_LENGTH_LUT_SIZE - 1) as f64;
+                self.eval((j as f64 - 1.0 + local) * step)
+            })
+            .collect()
+    }
+
+    const FLATTEN_MAX_DEPTH: usize = 16;
+
+    /// Halves the curve until every chord is shorter than `max_len`.
+    pub fn flatten_max_length(&self, max_len: f64) -> PolyLine {
+        self.flatten_by(|curve| curve.chord_length() >= max_len)
+    }
+
+    /// Like [`Bezier::flatten_max_length`], but also keeps halving while a
+    /// piece strays further than `tolerance` from its chord.
+    pub fn flatten_max_length_and_tolerance(&self, max_len: f64, tolerance: f64) -> PolyLine {
+        self.flatten_by(|curve| curve.chord_length() >= max_len || curve.flatness() > tolerance)
+    }
+
+    /// Halves the curve until the tangent turns by at most `max_angle_rad`
+    /// across every piece.
+    pub fn flatten_max_angle(&self, max_angle_rad: f64) -> PolyLine {
+        self.flatten_by(|curve| curve.turning_angle() > max_angle_rad)
+    }
+
+    fn chord_length(&self) -> f64 {
+        (self.end - self.start).magnitude()
+    }
+
+    fn turning_angle(&self) -> f64 {
+        let (incoming, outgoing) = (self.middle - self.start, self.end - self.middle);
+        if incoming.magnitude2() == 0.0 || outgoing.magnitude2() == 0.0 {
+            return 0.0;
+        }
+        incoming.angle(outgoing).0.abs()
+    }
+
+    // Largest distance between the curve and the chord at the same parameter.
+    fn flatness(&self) -> f64 {
+        (self.middle - (self.start + self.end) / 2.0).magnitude() / 2.0
+    }
+
+    fn flatten_by(&self, should_split: impl Fn(&Bezier) -> bool) -> PolyLine {
+        let mut points = vec![self.start];
+        self.flatten_into(&should_split, Self::FLATTEN_MAX_DEPTH, &mut points);
+        PolyLine { points }
+    }
+
+    fn flatten_into(
+        &self,
+        should_split: &impl Fn(&Bezier) -> bool,
+        depth: usize,
+        out: &mut Vec<Vector2>,
+    ) {
+        if depth > 0 && should_split(self) {
+            let (first, second) = self.split(0.5);
+            first.flatten_into(should_split, depth - 1, out);
+            second.flatten_into(should_split, depth - 1, out);
+        } else {
+            out.push(self.end);
+        }
+    }
+
+    /// The pieces of the curve inside the rectangle, split where it crosses
+    /// the boundary.
+    pub fn clip_rect(&self, min: Vector2, max: Vector2) -> Vec<Bezier> {
+        let poly = self.to_poly();
+        let crossings: Vec<f64> = [
+            (poly.a.x, poly.b.x, poly.c.x - min.x),
+            (poly.a.x, poly.b.x, poly.c.x - max.x),
+            (poly.a.y, poly.b.y, poly.c.y - min.y),
+            (poly.a.y, poly.b.y, poly.c.y - max.y),
+        ]
+        .into_iter()
+        .flat_map(|(a, b, c)| quadratic_roots(a, b, c))
+        .filter(|t| *t > 0.0 && *t < 1.0)
+        .collect();
+        let inside = |p: Vector2| p.x >= min.x && p.x <= max.x && p.y >= min.y && p.y <= max.y;
+        if crossings.is_empty() {
+            return if inside(self.eval(0.5)) {
+                vec![*self]
+            } else {
+                vec![]
+            };
+        }
+        self.split_many(&crossings)
+            .into_iter()
+            .filter(|piece| inside(piece.eval(0.5)))
+            .collect()
+    }
+
+    pub fn split_many(&self, ts: &[f64]) -> Vec<Bezier> {
+        let mut bounds: Vec<f64> = ts
+            .iter()
+            .filter(|t| !t.is_nan())
+            .map(|t| t.clamp(0.0, 1.0))
+            .chain([0.0, 1.0])
+            .collect();
+        bounds.sort_by(f64::total_cmp);
+        bounds.dedup();
+        bounds
+            .windows(2)
+            .map(|pair| self.trim(pair[0], pair[1]))
+            .collect()
+    }
 }
 
-impl PolyLine {
+#[derive(Clone, Copy, PartialEq)]
+pub struct QuadraticPoly<T: BaseFloat = f64> {
+    pub a: Vector2<T>,
+    pub b: Vector2<T>,
+    pub c: Vector2<T>,
+}
+
+impl<T: BaseFloat> QuadraticPoly<T> {
+    pub fn eval(&self, t: T) -> Vector2<T> {
+        (self.a * t + self.b) * t + self.c
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct CubicBezier<T: BaseFloat = f64> {
+    pub start: Vector2<T>,
+    pub control1: Vector2<T>,
+    pub control2: Vector2<T>,
+    pub end: Vector2<T>,
+}
+
+impl<T: BaseFloat> CubicBezier<T> {
+    pub fn subdivide(&self, count: usize) -> PolyLine<T> {
+        PolyLine {
+            points: self.iter_subdivided(count).collect(),
+        }
+    }
+
+    /// The points `subdivide` would give, computed as they are consumed.
+    pub fn iter_subdivided(&self, count: usize) -> impl Iterator<Item = Vector2<T>> {
+        let curve = *self;
+        (0..count).map(move |i| curve.eval(parameter(i, count)))
+    }
+
+    pub fn new(
+        start: Vector2<T>,
+        control1: Vector2<T>,
+        control2: Vector2<T>,
+        end: Vector2<T>,
+    ) -> Self {
+        Self {
+            start,
+            control1,
+            control2,
+            end,
+        }
+    }
+
+    pub fn line(start: Vector2<T>, end: Vector2<T>) -> Self {
+        let third = T::one() / T::from(3).unwrap();
+        Self::new(
+            start,
+            Bezier::lerp(start, end, third),
+            Bezier::lerp(start, end, third + third),
+            end,
+        )
+    }
+
+    pub fn eval(&self, t: T) -> Vector2<T> {
+        let a = Bezier::new(self.start, self.control1, self.control2).eval(t);
+        let b = Bezier::new(self.control1, self.control2, self.end).eval(t);
+        Bezier::lerp(a, b, t)
+    }
+
+    pub fn derivative(&self, t: T) -> Vector2<T> {
+        Bezier::new(
+            self.control1 - self.start,
+            self.control2 - self.control1,
+            self.end - self.control2,
+        )
+        .eval(t)
+            * T::from(3).unwrap()
+    }
+}
+
+impl CubicBezier {
+    /// The y of the point at `x`, for curves whose x only increases, such as
+    /// CSS easing curves.
+    pub fn y_at_x(&self, x: f64) -> f64 {
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..Bezier::Y_AT_X_ITERATIONS {
+            let middle = (low + high) / 2.0;
+            if self.eval(middle).x < x {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        self.eval((low + high) / 2.0).y
+    }
+
+    /// Parameters in (0, 1) where the curvature changes sign, in ascending order.
+    pub fn inflections(&self) -> Vec<f64> {
+        let cross = |u: Vector2, v: Vector2| u.x * v.y - u.y * v.x;
+        let a = self.control1 - self.start;
+        let b = self.control2 - self.control1;
+        let c = self.end - self.control2;
+        let (p, q) = (a - b * 2.0 + c, b - a);
+        // The cross product of the first and second derivatives, up to a
+        // positive factor: -cross(p, q) t^2 + cross(a, p) t + cross(a, q).
+        let (qa, qb, qc) = (-cross(p, q), cross(a, p), cross(a, q));
+
+        let mut roots = quadratic_roots(qa, qb, qc);
+        roots.retain(|t| *t > 0.0 && *t < 1.0);
+        roots.sort_by(f64::total_cmp);
+        roots.dedup();
+        roots
+    }
+
+    // Largest speed at a cusp relative to the size of the control polygon.
+    const CUSP_TOLERANCE: f64 = 1e-9;
+
+    /// The parameter in (0, 1) where the velocity vanishes, if any. A
+    /// quadratic elevated to a cubic has one when its control points are
+    /// collinear with the middle one outside the ends.
+    pub fn cusp(&self) -> Option<f64> {
+        let a = self.control1 - self.start;
+        let b = self.control2 - self.control1;
+        let c = self.end - self.control2;
+        let scale = [a, b, c]
+            .iter()
+            .map(|difference| difference.magnitude())
+            .fold(0.0, f64::max);
+        if scale == 0.0 {
+            return None;
+        }
+        // A third of the derivative is p t^2 + 2q t + a, so a cusp is a common
+        // root of both components.
+        let (p, q) = (a - b * 2.0 + c, b - a);
+        quadratic_roots(p.x, 2.0 * q.x, a.x)
+            .into_iter()
+            .chain(quadratic_roots(p.y, 2.0 * q.y, a.y))
+            .filter(|t| *t > 0.0 && *t < 1.0)
+            .find(|&t| self.derivative(t).magnitude() <= 3.0 * Self::CUSP_TOLERANCE * scale)
+    }
+
+    /// Best-fit quadratic, from projecting the control points onto one, and
+    /// the largest distance between it and the cubic.
+    pub fn reduce_to_quadratic(&self) -> (Bezier, f64) {
+        let middle = ((self.control1 + self.control2) * 3.0 - self.start - self.end) / 4.0;
+        // The difference between the curves is D/2 * t(1 - t)(1 - 2t), with D
+        // the third difference of the control points, peaking at sqrt(3)/18.
+        let third_difference = self.end - self.control2 * 3.0 + self.control1 * 3.0 - self.start;
+        let error = third_difference.magnitude() * 3f64.sqrt() / 36.0;
+        (Bezier::new(self.start, middle, self.end), error)
+    }
+}
+
+#[derive(Debug)]
+pub struct PolyLine<T: BaseFloat = f64> {
+    pub points: Vec<Vector2<T>>,
+}
+
+impl<T: BaseFloat> PolyLine<T> {
     pub fn new() -> Self {
         Self { points: Vec::new() }
     }
+
+    pub fn segments(&self) -> impl Iterator<Item = (Vector2<T>, Vector2<T>)> + '_ {
+        self.points.windows(2).map(|pair| (pair[0], pair[1]))
+    }
+
+    /// Compares points pairwise, allowing each to be off by up to `eps`.
+    pub fn approx_eq(&self, other: &Self, eps: T) -> bool {
+        self.points.len() == other.points.len()
+            && self
+                .points
+                .iter()
+                .zip(&other.points)
+                .all(|(&a, &b)| (a - b).magnitude() <= eps)
+    }
+}
+
+impl PolyLine {
+    /// The line as SVG path data, in its own coordinates.
+    pub fn to_svg_path(&self) -> String {
+        use std::fmt::Write;
+
+        let mut path = String::new();
+        for (i, point) in self.points.iter().enumerate() {
+            let command = if i == 0 { "M" } else { " L" };
+            write!(path, "{} {} {}", command, point.x, point.y).unwrap();
+        }
+        path
+    }
+
+    /// Samples `y = f(x)` at `samples` evenly spaced x from `x0` to `x1`,
+    /// always including both ends.
+    pub fn from_function(f: impl Fn(f64) -> f64, x0: f64, x1: f64, samples: usize) -> PolyLine {
+        let count = samples.max(2);
+        (0..count)
+            .map(|i| {
+                let x = x0 + (x1 - x0) * parameter::<f64>(i, count);
+                vec2(x, f(x))
+            })
+            .collect()
+    }
+
+    pub fn length(&self) -> f64 {
+        self.cumulative_lengths().last().copied().unwrap_or(0.0)
+    }
+
+    pub fn cumulative_lengths(&self) -> Vec<f64> {
+        let mut length = 0.0;
+        let mut lengths = Vec::with_capacity(self.points.len());
+        for (i, point) in self.points.iter().enumerate() {
+            if i > 0 {
+                length += (point - self.points[i - 1]).magnitude();
+            }
+            lengths.push(length);
+        }
+        lengths
+    }
+
+    pub fn resample(&self, spacing: f64) -> PolyLine {
+        PolyLine {
+            points: self.resampled(spacing).collect(),
+        }
+    }
+
+    /// The points `resample` would give, computed as they are consumed.
+    pub fn resampled(&self, spacing: f64) -> impl Iterator<Item = Vector2> + '_ {
+        let points = &self.points;
+        // Without a positive spacing only the first point is given.
+        let mut segment = if spacing > 0.0 { 0 } else { points.len() };
+        let mut next = spacing;
+        let mut travelled = 0.0;
+        let along = std::iter::from_fn(move || {
+            while segment + 1 < points.len() {
+                let (start, end) = (points[segment], points[segment + 1]);
+                let length = (end - start).magnitude();
+                if length > 0.0 && next <= travelled + length {
+                    let point = Bezier::lerp(start, end, (next - travelled) / length);
+                    next += spacing;
+                    return Some(point);
+                }
+                travelled += length;
+                segment += 1;
+            }
+            None
+        });
+        points.first().copied().into_iter().chain(along)
+    }
+
+    pub fn distance_to(&self, p: Vector2) -> f64 {
+        match self.points.as_slice() {
+            [] => f64::INFINITY,
+            [point] => (p - point).magnitude(),
+            points => points
+                .windows(2)
+                .map(|pair| (p - closest_point_on_segment(p, pair[0], pair[1]).1).magnitude())
+                .fold(f64::INFINITY, f64::min),
+        }
+    }
+
+    /// The segment index, the parameter within that segment and the closest
+    /// point on the line to `p`; ties go to the lower index. Panics if the
+    /// line is empty.
+    pub fn project(&self, p: Vector2) -> (usize, f64, Vector2) {
+        match self.points.as_slice() {
+            [] => panic!("cannot project onto an empty line"),
+            [point] => (0, 0.0, *point),
+            points => points
+                .windows(2)
+                .enumerate()
+                .map(|(i, pair)| {
+                    let (t, foot) = closest_point_on_segment(p, pair[0], pair[1]);
+                    (i, t, foot)
+                })
+                .min_by(|a, b| (p - a.2).magnitude2().total_cmp(&(p - b.2).magnitude2()))
+                .unwrap(),
+        }
+    }
+
+    /// The largest distance from a point of this line to `other`.
+    pub fn hausdorff_directed(&self, other: &PolyLine) -> f64 {
+        self.points
+            .iter()
+            .map(|&point| other.distance_to(point))
+            .fold(0.0, f64::max)
+    }
+
+    pub fn hausdorff(&self, other: &PolyLine) -> f64 {
+        self.hausdorff_directed(other)
+            .max(other.hausdorff_directed(self))
+    }
+
+    pub fn is_within(&self, p: Vector2, width: f64) -> bool {
+        self.distance_to(p) <= width
+    }
+
+    pub fn fill_render_data(&self) -> RenderData {
+        use geo::TriangulateEarcut;
+
+        let polygon = geo::Polygon::new(self.to_line_string(), vec![]);
+        triangulation_render_data(polygon.earcut_triangles_raw())
+    }
+
+    fn to_line_string(&self) -> geo::LineString<f64> {
+        self.points.iter().map(|point| (point.x, point.y)).collect()
+    }
+
+    /// Repeatedly drops the interior point that spans the smallest triangle
+    /// with its neighbors until every remaining triangle covers `min_area`.
+    pub fn simplify_visvalingam(&self, min_area: f64) -> PolyLine {
+        use geo::SimplifyVw;
+
+        self.to_line_string()
+            .simplify_vw(&min_area)
+            .0
+            .iter()
+            .map(|coord| vec2(coord.x, coord.y))
+            .collect()
+    }
+
+    /// The heading at every point, from the average of the unit directions of
+    /// the segments meeting there.
+    pub fn tangent_angles(&self) -> Vec<f64> {
+        let count = self.points.len();
+        let direction = |i: usize| {
+            let segment = self.points[i + 1] - self.points[i];
+            if segment.magnitude2() > 0.0 {
+                segment.normalize()
+            } else {
+                segment
+            }
+        };
+        (0..count)
+            .map(|i| {
+                let incoming = if i > 0 {
+                    direction(i - 1)
+                } else {
+                    vec2(0.0, 0.0)
+                };
+                let outgoing = if i + 1 < count {
+                    direction(i)
+                } else {
+                    vec2(0.0, 0.0)
+                };
+                let heading = if (incoming + outgoing).magnitude2() > 0.0 {
+                    incoming + outgoing
+                } else {
+                    outgoing
+                };
+                heading.y.atan2(heading.x)
+            })
+            .collect()
+    }
+
+    const CLOSED_TOLERANCE: f64 = 1e-9;
+
+    /// The sum of the signed angles between consecutive segments, positive
+    /// for counterclockwise turns. When the line ends where it starts, the
+    /// turn at that point counts as well.
+    pub fn total_turning(&self) -> f64 {
+        let mut segments: Vec<Vector2> = self
+            .points
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .filter(|segment| segment.magnitude2() > 0.0)
+            .collect();
+        let closed = match (self.points.first(), self.points.last()) {
+            (Some(&first), Some(&last)) => (last - first).magnitude() <= Self::CLOSED_TOLERANCE,
+            _ => false,
+        };
+        if segments.len() > 1 && closed {
+            segments.push(segments[0]);
+        }
+        segments
+            .windows(2)
+            .map(|pair| {
+                let (incoming, outgoing) = (pair[0], pair[1]);
+                let cross = incoming.x * outgoing.y - incoming.y * outgoing.x;
+                cross.atan2(incoming.dot(outgoing))
+            })
+            .sum()
+    }
+
+    /// Averages every point with up to `window` neighbors on each side. The
+    /// window shrinks symmetrically near the ends, so the endpoints stay put.
+    pub fn moving_average(&self, window: usize) -> PolyLine {
+        let count = self.points.len();
+        (0..count)
+            .map(|i| {
+                let radius = window.min(i).min(count - 1 - i);
+                let neighbors = &self.points[i - radius..=i + radius];
+                neighbors
+                    .iter()
+                    .fold(vec2(0.0, 0.0), |sum, &point| sum + point)
+                    / neighbors.len() as f64
+            })
+            .collect()
+    }
+
+    pub fn chaikin(&self, iterations: usize, closed: bool) -> PolyLine {
+        let mut points = self.points.clone();
+        for _ in 0..iterations {
+            if points.len() < 2 {
+                break;
+            }
+            let segment_count = if closed {
+                points.len()
+            } else {
+                points.len() - 1
+            };
+            let mut next = Vec::with_capacity(segment_count * 2 + 2);
+            if !closed {
+                next.push(points[0]);
+            }
+            for i in 0..segment_count {
+                let start = points[i];
+                let end = points[(i + 1) % points.len()];
+                next.push(Bezier::lerp(start, end, 0.25));
+                next.push(Bezier::lerp(start, end, 0.75));
+            }
+            if !closed {
+                next.push(points[points.len() - 1]);
+            }
+            points = next;
+        }
+        PolyLine { points }
+    }
+}
+
+impl<T: BaseFloat> Default for PolyLine<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a point is inside by the winding number of the contours around
+/// it being nonzero, or odd.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+/// Fills non-intersecting contours with the even-odd rule: a contour nested
+/// inside an odd number of others cuts a hole in its enclosing contour.
+pub fn fill_even_odd(contours: &[PolyLine]) -> RenderData {
+    use geo::{Contains, TriangulateEarcut};
+
+    let rings: Vec<_> = contours
+        .iter()
+        .filter(|contour| contour.points.len() >= 3)
+        .map(|contour| geo::Polygon::new(contour.to_line_string(), vec![]))
+        .collect();
+    let parents: Vec<Vec<usize>> = rings
+        .iter()
+        .enumerate()
+        .map(|(i, ring)| {
+            let point = ring.exterior().0[0];
+            (0..rings.len())
+                .filter(|&j| j != i && rings[j].contains(&point))
+                .collect()
+        })
+        .collect();
+
+    let mut result = RenderData::new();
+    for (i, ring) in rings.iter().enumerate() {
+        if parents[i].len() % 2 == 1 {
+            continue;
+        }
+        let holes = (0..rings.len())
+            .filter(|&j| parents[j].len() == parents[i].len() + 1 && parents[j].contains(&i))
+            .map(|j| rings[j].exterior().clone())
+            .collect();
+        let polygon = geo::Polygon::new(ring.exterior().clone(), holes);
+        result = result.merge(triangulation_render_data(polygon.earcut_triangles_raw()));
+    }
+    result
+}
+
+/// The union of two simple polygons: the outline of every separate piece,
+/// each followed by the holes in it. Like the input, the contours don't
+/// repeat their first point at the end.
+pub fn polygon_union(a: &[Vector2], b: &[Vector2]) -> Vec<Vec<Vector2>> {
+    use geo::BooleanOps;
+
+    let polygon = |points: &[Vector2]| {
+        let ring = if points.len() >= 3 {
+            points.iter().map(|point| (point.x, point.y)).collect()
+        } else {
+            geo::LineString::new(vec![])
+        };
+        geo::Polygon::new(ring, vec![])
+    };
+    polygon(a)
+        .union(&polygon(b))
+        .iter()
+        .flat_map(|piece| std::iter::once(piece.exterior()).chain(piece.interiors()))
+        .map(ring_points)
+        .collect()
+}
+
+fn convex_hull(points: &[Vector2]) -> Vec<Vector2> {
+    use geo::ConvexHull;
+
+    let points: geo::MultiPoint<f64> = points.iter().map(|point| (point.x, point.y)).collect();
+    ring_points(points.convex_hull().exterior())
+}
+
+// The points of a closed geo ring, without the repeated first one.
+fn ring_points(ring: &geo::LineString<f64>) -> Vec<Vector2> {
+    ring.0[..ring.0.len().saturating_sub(1)]
+        .iter()
+        .map(|coord| vec2(coord.x, coord.y))
+        .collect()
+}
+
+// Real roots of a t^2 + b t + c, falling back to the linear equation when
+// `a` vanishes.
+fn quadratic_roots(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() <= f64::EPSILON {
+        if b.abs() <= f64::EPSILON {
+            vec![]
+        } else {
+            vec![-c / b]
+        }
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            vec![]
+        } else {
+            let root = discriminant.sqrt();
+            vec![(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+        }
+    }
+}
+
+// Real roots of a t^3 + b t^2 + c t + d, using the trigonometric form when
+// there are three of them.
+fn cubic_roots(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if a.abs() <= f64::EPSILON {
+        return quadratic_roots(b, c, d);
+    }
+    let (b, c, d) = (b / a, c / a, d / a);
+    // Substituting t = x - b / 3 gives the depressed cubic x^3 + p x + q.
+    let p = c - b * b / 3.0;
+    let q = 2.0 * b * b * b / 27.0 - b * c / 3.0 + d;
+    let shift = -b / 3.0;
+    let discriminant = q * q / 4.0 + p * p * p / 27.0;
+    if discriminant > 0.0 {
+        let root = discriminant.sqrt();
+        vec![(-q / 2.0 + root).cbrt() + (-q / 2.0 - root).cbrt() + shift]
+    } else if p == 0.0 {
+        vec![shift]
+    } else {
+        let radius = 2.0 * (-p / 3.0).sqrt();
+        let angle = (3.0 * q / (p * radius)).clamp(-1.0, 1.0).acos() / 3.0;
+        (0..3)
+            .map(|k| radius * (angle - std::f64::consts::TAU * k as f64 / 3.0).cos() + shift)
+            .collect()
+    }
+}
+
+fn closest_point_on_segment(p: Vector2, start: Vector2, end: Vector2) -> (f64, Vector2) {
+    let direction = end - start;
+    let length2 = direction.magnitude2();
+    if length2 == 0.0 {
+        return (0.0, start);
+    }
+    let t = ((p - start).dot(direction) / length2).clamp(0.0, 1.0);
+    (t, Bezier::lerp(start, end, t))
+}
+
+fn triangulation_render_data(
+    triangulation: geo::algorithm::triangulate_earcut::RawTriangulation<f64>,
+) -> RenderData {
+    RenderData {
+        vertices: triangulation
+            .vertices
+            .chunks_exact(2)
+            .map(|xy| Vertex::new_f64([xy[0], xy[1]]))
+            .collect(),
+        indices: triangulation
+            .triangle_indices
+            .into_iter()
+            .map(|i| i as u32)
+            .collect(),
+    }
+}
+
+impl<T: BaseFloat + std::fmt::Display> std::fmt::Display for Bezier<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Bezier(({}, {}), ({}, {}), ({}, {}))",
+            self.start.x, self.start.y, self.middle.x, self.middle.y, self.end.x, self.end.y
+        )
+    }
+}
+
+impl<T: BaseFloat> From<[Vector2<T>; 3]> for Bezier<T> {
+    fn from([start, middle, end]: [Vector2<T>; 3]) -> Self {
+        Self::new(start, middle, end)
+    }
+}
+
+impl<T: BaseFloat> From<Bezier<T>> for CubicBezier<T> {
+    fn from(curve: Bezier<T>) -> Self {
+        let two_thirds = T::from(2).unwrap() / T::from(3).unwrap();
+        Self::new(
+            curve.start,
+            Bezier::lerp(curve.start, curve.middle, two_thirds),
+            Bezier::lerp(curve.end, curve.middle, two_thirds),
+            curve.end,
+        )
+    }
+}
+
+impl<T: BaseFloat> FromIterator<Vector2<T>> for PolyLine<T> {
+    fn from_iter<I: IntoIterator<Item = Vector2<T>>>(iter: I) -> Self {
+        Self {
+            points: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T: BaseFloat> std::ops::Index<usize> for PolyLine<T> {
+    type Output = Vector2<T>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.points[index]
+    }
 }
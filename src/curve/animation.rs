@@ -0,0 +1,132 @@
+use super::vec2;
+use super::{Bezier, CubicBezier};
+
+/// Curves at given times, in seconds, with the control points moving from
+/// one to the next as timed by the easing of that span.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Animation {
+    // Sorted by time, which `sample` relies on.
+    keyframes: Vec<(f64, Bezier)>,
+    // Easing from each keyframe to the next, linear for the ones missing.
+    easings: Vec<Easing>,
+}
+
+impl Animation {
+    /// Sorts the keyframes by time.
+    pub fn new(mut keyframes: Vec<(f64, Bezier)>) -> Self {
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            keyframes,
+            easings: Vec::new(),
+        }
+    }
+
+    /// Easing from each keyframe to the next, linear for the ones missing.
+    pub fn with_easings(self, easings: Vec<Easing>) -> Self {
+        Self { easings, ..self }
+    }
+
+    /// The keyframes, sorted by time.
+    pub fn keyframes(&self) -> &[(f64, Bezier)] {
+        &self.keyframes
+    }
+
+    pub fn easings(&self) -> &[Easing] {
+        &self.easings
+    }
+
+    /// The time of the last keyframe.
+    pub fn duration(&self) -> f64 {
+        self.keyframes.last().map_or(0.0, |&(time, _)| time)
+    }
+
+    /// The curve at `time`, holding the first and last keyframes outside of
+    /// their range. Panics without keyframes.
+    pub fn sample(&self, time: f64) -> Bezier {
+        let next = self
+            .keyframes
+            .partition_point(|&(keyframe_time, _)| keyframe_time <= time);
+        if next == 0 {
+            return self.keyframes[0].1;
+        }
+        let (start_time, start) = self.keyframes[next - 1];
+        let Some(&(end_time, end)) = self.keyframes.get(next) else {
+            return start;
+        };
+        let easing = self.easings.get(next - 1).unwrap_or(&Easing::LINEAR);
+        let t = easing.apply((time - start_time) / (end_time - start_time));
+        Bezier::new(
+            Bezier::lerp(start.start, end.start, t),
+            Bezier::lerp(start.middle, end.middle, t),
+            Bezier::lerp(start.end, end.end, t),
+        )
+    }
+}
+
+/// Maps the fraction of time elapsed to the fraction of the way travelled
+/// like CSS `cubic-bezier(x1, y1, x2, y2)`, with a curve from (0, 0) to
+/// (1, 1) that is read as y over x.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Easing {
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+}
+
+impl Easing {
+    pub const LINEAR: Easing = Easing {
+        x1: 0.0,
+        y1: 0.0,
+        x2: 1.0,
+        y2: 1.0,
+    };
+    pub const EASE: Easing = Easing {
+        x1: 0.25,
+        y1: 0.1,
+        x2: 0.25,
+        y2: 1.0,
+    };
+    pub const EASE_IN: Easing = Easing {
+        x1: 0.42,
+        y1: 0.0,
+        x2: 1.0,
+        y2: 1.0,
+    };
+    pub const EASE_OUT: Easing = Easing {
+        x1: 0.0,
+        y1: 0.0,
+        x2: 0.58,
+        y2: 1.0,
+    };
+    pub const EASE_IN_OUT: Easing = Easing {
+        x1: 0.42,
+        y1: 0.0,
+        x2: 0.58,
+        y2: 1.0,
+    };
+
+    /// As in CSS, `x1` and `x2` are clamped to [0, 1] so that the curve is a
+    /// function of x.
+    pub fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64) -> Self {
+        Self {
+            x1: x1.clamp(0.0, 1.0),
+            y1,
+            x2: x2.clamp(0.0, 1.0),
+            y2,
+        }
+    }
+
+    pub fn apply(&self, t: f64) -> f64 {
+        if t <= 0.0 || t >= 1.0 {
+            return t.clamp(0.0, 1.0);
+        }
+        CubicBezier::new(
+            vec2(0.0, 0.0),
+            vec2(self.x1, self.y1),
+            vec2(self.x2, self.y2),
+            vec2(1.0, 1.0),
+        )
+        .y_at_x(t)
+    }
+}
@@ -0,0 +1,50 @@
+use cgmath::BaseFloat;
+
+use super::parameter;
+
+type Vector3<T = f64> = cgmath::Vector3<T>;
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Bezier3<T: BaseFloat = f64> {
+    pub start: Vector3<T>,
+    pub middle: Vector3<T>,
+    pub end: Vector3<T>,
+}
+
+impl<T: BaseFloat> Bezier3<T> {
+    pub fn subdivide(&self, count: usize) -> PolyLine3<T> {
+        PolyLine3 {
+            points: (0..count).map(|i| self.eval(parameter(i, count))).collect(),
+        }
+    }
+
+    pub fn new(start: Vector3<T>, middle: Vector3<T>, end: Vector3<T>) -> Self {
+        Self { start, middle, end }
+    }
+
+    pub fn eval(&self, t: T) -> Vector3<T> {
+        let a = Self::lerp(self.start, self.middle, t);
+        let b = Self::lerp(self.middle, self.end, t);
+        Self::lerp(a, b, t)
+    }
+
+    pub fn lerp(start: Vector3<T>, end: Vector3<T>, t: T) -> Vector3<T> {
+        end * t + start * (T::one() - t)
+    }
+}
+
+pub struct PolyLine3<T: BaseFloat = f64> {
+    pub points: Vec<Vector3<T>>,
+}
+
+impl<T: BaseFloat> PolyLine3<T> {
+    pub fn new() -> Self {
+        Self { points: Vec::new() }
+    }
+}
+
+impl<T: BaseFloat> Default for PolyLine3<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
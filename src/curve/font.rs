@@ -0,0 +1,79 @@
+use ttf_parser::{Face, GlyphId, OutlineBuilder};
+
+use super::vec2;
+use super::{Bezier, CubicBezier, Spline, Vector2};
+
+impl Spline {
+    /// One closed spline per contour of the glyph, in font units with y
+    /// pointing up. ttf-parser resolves the on-curve points implied between
+    /// consecutive off-curve ones, and the quadratics are elevated to cubics.
+    /// Glyphs without an outline give no splines.
+    pub fn from_glyph(face: &Face, glyph_id: GlyphId) -> Vec<Spline> {
+        let mut builder = GlyphBuilder {
+            contours: Vec::new(),
+            segments: Vec::new(),
+            start: vec2(0.0, 0.0),
+            last: vec2(0.0, 0.0),
+        };
+        face.outline_glyph(glyph_id, &mut builder);
+        builder.close();
+        builder.contours
+    }
+}
+
+struct GlyphBuilder {
+    contours: Vec<Spline>,
+    segments: Vec<CubicBezier>,
+    start: Vector2,
+    last: Vector2,
+}
+
+impl GlyphBuilder {
+    fn push(&mut self, segment: CubicBezier) {
+        self.last = segment.end;
+        self.segments.push(segment);
+    }
+}
+
+impl OutlineBuilder for GlyphBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.start = vec2(x as f64, y as f64);
+        self.last = self.start;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.push(CubicBezier::line(self.last, vec2(x as f64, y as f64)));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let curve = Bezier::new(
+            self.last,
+            vec2(x1 as f64, y1 as f64),
+            vec2(x as f64, y as f64),
+        );
+        self.push(curve.into());
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.push(CubicBezier::new(
+            self.last,
+            vec2(x1 as f64, y1 as f64),
+            vec2(x2 as f64, y2 as f64),
+            vec2(x as f64, y as f64),
+        ));
+    }
+
+    // Also called on a new contour or at the end, in case the outline left
+    // the previous one open.
+    fn close(&mut self) {
+        if self.segments.is_empty() {
+            return;
+        }
+        if self.last != self.start {
+            self.push(CubicBezier::line(self.last, self.start));
+        }
+        let segments = std::mem::take(&mut self.segments);
+        self.contours.push(Spline { segments });
+    }
+}
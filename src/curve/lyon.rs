@@ -0,0 +1,84 @@
+use lyon_path::{math::point, Event, Path};
+
+use super::vec2;
+use super::{Bezier, CubicBezier, Spline, Vector2};
+
+fn to_point(v: Vector2) -> lyon_path::math::Point {
+    point(v.x as f32, v.y as f32)
+}
+
+fn from_point(p: lyon_path::math::Point) -> Vector2 {
+    vec2(p.x as f64, p.y as f64)
+}
+
+impl From<&Bezier> for Path {
+    fn from(curve: &Bezier) -> Self {
+        let mut builder = Path::builder();
+        builder.begin(to_point(curve.start));
+        builder.quadratic_bezier_to(to_point(curve.middle), to_point(curve.end));
+        builder.end(false);
+        builder.build()
+    }
+}
+
+/// Segments that don't start where the previous one ended begin a new
+/// subpath.
+impl From<&Spline> for Path {
+    fn from(spline: &Spline) -> Self {
+        let mut builder = Path::builder();
+        let mut last = None;
+        for segment in &spline.segments {
+            if last != Some(segment.start) {
+                if last.is_some() {
+                    builder.end(false);
+                }
+                builder.begin(to_point(segment.start));
+            }
+            builder.cubic_bezier_to(
+                to_point(segment.control1),
+                to_point(segment.control2),
+                to_point(segment.end),
+            );
+            last = Some(segment.end);
+        }
+        if last.is_some() {
+            builder.end(false);
+        }
+        builder.build()
+    }
+}
+
+/// Lines and quadratics are elevated to cubics, and closed subpaths get a
+/// line back to their start.
+impl From<&Path> for Spline {
+    fn from(path: &Path) -> Self {
+        let segments = path
+            .iter()
+            .filter_map(|event| match event {
+                Event::Begin { .. } => None,
+                Event::Line { from, to } => {
+                    Some(CubicBezier::line(from_point(from), from_point(to)))
+                }
+                Event::Quadratic { from, ctrl, to } => Some(CubicBezier::from(Bezier::new(
+                    from_point(from),
+                    from_point(ctrl),
+                    from_point(to),
+                ))),
+                Event::Cubic {
+                    from,
+                    ctrl1,
+                    ctrl2,
+                    to,
+                } => Some(CubicBezier::new(
+                    from_point(from),
+                    from_point(ctrl1),
+                    from_point(ctrl2),
+                    from_point(to),
+                )),
+                Event::End { last, first, close } => (close && last != first)
+                    .then(|| CubicBezier::line(from_point(last), from_point(first))),
+            })
+            .collect();
+        Spline { segments }
+    }
+}
@@ -0,0 +1,76 @@
+use cgmath::BaseFloat;
+
+use super::parameter;
+use super::vec2;
+use super::PolyLine;
+use super::Vector2;
+
+#[derive(Clone, PartialEq)]
+pub struct RationalBezier<T: BaseFloat = f64> {
+    pub control: Vec<Vector2<T>>,
+    pub weights: Vec<T>,
+}
+
+impl<T: BaseFloat> RationalBezier<T> {
+    pub fn new(control: Vec<Vector2<T>>, weights: Vec<T>) -> Self {
+        assert!(
+            !control.is_empty(),
+            "a rational Bezier needs control points"
+        );
+        assert_eq!(control.len(), weights.len());
+        Self { control, weights }
+    }
+
+    pub fn subdivide(&self, count: usize) -> PolyLine<T> {
+        PolyLine {
+            points: (0..count).map(|i| self.eval(parameter(i, count))).collect(),
+        }
+    }
+
+    /// Panics without control points or without a weight for each of them,
+    /// which the public fields allow past `new`.
+    pub fn eval(&self, t: T) -> Vector2<T> {
+        assert!(
+            !self.control.is_empty(),
+            "a rational Bezier needs control points"
+        );
+        assert_eq!(self.control.len(), self.weights.len());
+        let mut points: Vec<(Vector2<T>, T)> = self
+            .control
+            .iter()
+            .zip(&self.weights)
+            .map(|(&point, &weight)| (point * weight, weight))
+            .collect();
+        while points.len() > 1 {
+            points = points
+                .windows(2)
+                .map(|pair| {
+                    (
+                        pair[0].0 * (T::one() - t) + pair[1].0 * t,
+                        pair[0].1 * (T::one() - t) + pair[1].1 * t,
+                    )
+                })
+                .collect();
+        }
+        let (numerator, denominator) = points[0];
+        numerator / denominator
+    }
+}
+
+impl RationalBezier {
+    /// An exact circular arc as a conic; `end_angle - start_angle` must be
+    /// below a half turn.
+    pub fn arc(center: Vector2, radius: f64, start_angle: f64, end_angle: f64) -> Self {
+        let half = (end_angle - start_angle) / 2.0;
+        let middle_angle = start_angle + half;
+        let point = |angle: f64| center + vec2(angle.cos(), angle.sin()) * radius;
+        Self::new(
+            vec![
+                point(start_angle),
+                center + vec2(middle_angle.cos(), middle_angle.sin()) * (radius / half.cos()),
+                point(end_angle),
+            ],
+            vec![1.0, half.cos(), 1.0],
+        )
+    }
+}
@@ -1,3 +1,5 @@
+use std::{cell::RefCell, ops::Range};
+
 use cgmath::InnerSpace;
 
 use super::vec2;
@@ -6,98 +8,252 @@ use super::Vector2;
 
 use crate::{vertex::RenderData, Vertex};
 
-pub struct ConnectionRenderer {}
+pub trait CurveRenderer {
+    /// Short label for the renderer, shown in the window title.
+    fn name(&self) -> &str;
+
+    fn render(&self, line: &PolyLine, width: f64) -> RenderData;
+
+    /// Appends the stroke to `out`. The built-in renderers write straight
+    /// into it, so reusing `out` across frames avoids reallocating; this
+    /// default goes through `render` and allocates.
+    fn render_into(&self, line: &PolyLine, width: f64, out: &mut RenderData) {
+        out.append(&self.render(line, width));
+    }
+}
+
+/// Which side of the path the stroke covers. `Inner` lies to the left of the
+/// direction of travel, which is the inside of a counter-clockwise contour.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum StrokeAlign {
+    #[default]
+    Center,
+    Inner,
+    Outer,
+}
+
+impl StrokeAlign {
+    fn offsets(self) -> (f64, f64) {
+        match self {
+            StrokeAlign::Center => (1.0, 1.0),
+            StrokeAlign::Inner => (0.0, 2.0),
+            StrokeAlign::Outer => (2.0, 0.0),
+        }
+    }
+}
+
+pub struct ConnectionRenderer {
+    align: StrokeAlign,
+    closed: bool,
+    // The closed loop, kept between calls so rendering one doesn't allocate.
+    closed_line: RefCell<PolyLine>,
+}
+
+impl Default for ConnectionRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CurveRenderer for ConnectionRenderer {
+    fn name(&self) -> &str {
+        "connection"
+    }
+
+    fn render(&self, line: &PolyLine, width: f64) -> RenderData {
+        let mut result = RenderData::new();
+        self.render_into(line, width, &mut result);
+        result
+    }
+
+    fn render_into(&self, line: &PolyLine, width: f64, out: &mut RenderData) {
+        let closed_line = &mut *self.closed_line.borrow_mut();
+        if self.closed && closed_loop_into(line, closed_line) {
+            let count = closed_line.points.len() - 3;
+            return self.render_ranges(closed_line, width, 2..count + 2, 1..count + 1, out);
+        }
+        let count = line.points.len();
+        self.render_ranges(line, width, 1..count, 1..count.saturating_sub(1), out)
+    }
+}
 
 impl ConnectionRenderer {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            align: StrokeAlign::Center,
+            closed: false,
+            closed_line: RefCell::new(PolyLine::new()),
+        }
+    }
+
+    pub fn with_align(self, align: StrokeAlign) -> Self {
+        Self { align, ..self }
     }
 
-    pub fn render(&self, line: &PolyLine, width: f64) -> RenderData {
+    pub fn with_closed(self, closed: bool) -> Self {
+        Self { closed, ..self }
+    }
+
+    /// Renders the stroke for `PrimitiveTopology::TriangleStrip`, with two
+    /// vertices per segment end. The triangles between consecutive segments
+    /// cover the joins, and `RenderData::STRIP_RESTART` ends the strip, so
+    /// the result isn't a triangle list and doesn't mix with one.
+    pub fn render_strip(&self, line: &PolyLine, width: f64) -> RenderData {
         let mut result = RenderData::new();
+        self.render_strip_into(line, width, &mut result);
+        result
+    }
 
-        for i in 1..line.points.len() {
-            result = result.merge(Self::get_segment_render_data(line, i, width));
+    pub fn render_strip_into(&self, line: &PolyLine, width: f64, out: &mut RenderData) {
+        let closed_line = &mut *self.closed_line.borrow_mut();
+        let closed = self.closed && closed_loop_into(line, closed_line);
+        let (line, segments) = if closed {
+            (&*closed_line, 2..closed_line.points.len() - 1)
+        } else {
+            (line, 1..line.points.len())
+        };
+        if segments.is_empty() {
+            return;
         }
-
-        for i in 1..line.points.len() - 1 {
-            result = result.merge(Self::get_connection_render_data(line, i, width));
+        for (i, start_v, end_v) in stroke_lengths(line, segments.clone()) {
+            if segments.contains(&i) {
+                let start_points = self.get_adjusted_start_points(line, i - 1, width);
+                let end_points = self.get_adjusted_end_points(line, i, width);
+                out.extend(
+                    segment_vertices(start_points, end_points, start_v, end_v),
+                    [0, 1, 2, 3],
+                );
+            }
         }
+        if closed {
+            // Back to the start of the first segment, bridging the last join.
+            let start_points = self.get_adjusted_start_points(line, segments.start - 1, width);
+            let [right, left, ..] = segment_vertices(start_points, start_points, 1.0, 1.0);
+            out.extend([right, left], [0, 1]);
+        }
+        out.indices.push(RenderData::STRIP_RESTART);
+    }
 
+    /// Lines `line_width` wide from every point to the offset positions
+    /// computed for the segments on either side of it, drawn as thin quads
+    /// so they show up with either pipeline.
+    pub fn debug_normals(&self, line: &PolyLine, width: f64, line_width: f64) -> RenderData {
+        let count = line.points.len();
+        let mut result = RenderData::new();
+        for i in 0..count {
+            let start = (i + 1 < count).then(|| self.get_start_points(line, i, width));
+            let end = (i > 0).then(|| self.get_end_points(line, i, width));
+            let point = line.points[i];
+            for offset in start
+                .into_iter()
+                .chain(end)
+                .flat_map(|(right, left)| [right, left])
+            {
+                let direction = offset - point;
+                if direction.magnitude2() == 0.0 {
+                    continue;
+                }
+                let side = vec2(-direction.y, direction.x).normalize() * (line_width / 2.0);
+                let vertices = [point + side, point - side, offset + side, offset - side]
+                    .map(|corner| Vertex::new_f64(corner.into()));
+                result.extend(vertices, [0, 2, 3, 0, 3, 1]);
+            }
+        }
         result
     }
 
-    fn get_segment_render_data(line: &PolyLine, i: usize, width: f64) -> RenderData {
-        let start_points = Self::get_adjusted_start_points(line, i - 1, width);
-        let end_points = Self::get_adjusted_end_points(line, i, width);
-        let vertices: Vec<Vertex> = [start_points.0, start_points.1, end_points.0, end_points.1]
-            .map(Vector2::into)
-            .map(Vertex::new_f64)
-            .into_iter()
-            .collect();
-        let indices: Vec<_> = vec![0, 2, 3, 0, 3, 1];
-        RenderData { vertices, indices }
-    }
-
-    fn get_connection_render_data(line: &PolyLine, i: usize, width: f64) -> RenderData {
-        let vertices: Vec<_> = match Self::get_connection(line, i, width) {
-            Some((intersection, false)) => {
-                vec![
-                    Self::get_end_points(line, i, width).1,
-                    intersection,
-                    Self::get_start_points(line, i, width).1,
-                ]
+    fn render_ranges(
+        &self,
+        line: &PolyLine,
+        width: f64,
+        segments: Range<usize>,
+        connections: Range<usize>,
+        out: &mut RenderData,
+    ) {
+        for (i, start_v, end_v) in stroke_lengths(line, segments.clone()) {
+            if segments.contains(&i) {
+                self.get_segment_render_data(line, i, width, start_v, end_v, out);
             }
-            Some((intersection, true)) => {
-                vec![
-                    Self::get_end_points(line, i, width).0,
-                    intersection,
-                    Self::get_start_points(line, i, width).0,
-                ]
+            if connections.contains(&i) {
+                self.get_connection_render_data(line, i, width, end_v, out);
             }
-            None => vec![],
-        }
-        .into_iter()
-        .map(Vector2::into)
-        .map(Vertex::new_f64)
-        .collect();
-        let indices = if vertices.is_empty() {
-            vec![]
-        } else {
-            vec![0, 1, 2]
-        };
-        RenderData { vertices, indices }
+        }
     }
 
-    fn get_adjusted_start_points(line: &PolyLine, i: usize, width: f64) -> (Vector2, Vector2) {
-        let start_points = Self::get_start_points(line, i, width);
+    fn get_segment_render_data(
+        &self,
+        line: &PolyLine,
+        i: usize,
+        width: f64,
+        start_v: f64,
+        end_v: f64,
+        out: &mut RenderData,
+    ) {
+        let start_points = self.get_adjusted_start_points(line, i - 1, width);
+        let end_points = self.get_adjusted_end_points(line, i, width);
+        let vertices = segment_vertices(start_points, end_points, start_v, end_v);
+        out.extend(vertices, [0, 2, 3, 0, 3, 1]);
+    }
+
+    fn get_connection_render_data(
+        &self,
+        line: &PolyLine,
+        i: usize,
+        width: f64,
+        v: f64,
+        out: &mut RenderData,
+    ) {
+        let vertices = match self.get_connection(line, i, width) {
+            Some((intersection, false)) => [
+                (self.get_end_points(line, i, width).1, 1.0),
+                (intersection, 0.0),
+                (self.get_start_points(line, i, width).1, 1.0),
+            ],
+            Some((intersection, true)) => [
+                (self.get_end_points(line, i, width).0, 0.0),
+                (intersection, 1.0),
+                (self.get_start_points(line, i, width).0, 0.0),
+            ],
+            None => return,
+        }
+        .map(|(point, u)| Vertex::new_f64(point.into()).with_uv([u, v as f32]));
+        out.extend(vertices, [0, 1, 2]);
+    }
+
+    fn get_adjusted_start_points(
+        &self,
+        line: &PolyLine,
+        i: usize,
+        width: f64,
+    ) -> (Vector2, Vector2) {
+        let start_points = self.get_start_points(line, i, width);
         if i == 0 {
             return start_points;
         }
-        match Self::get_connection(line, i, width) {
+        match self.get_connection(line, i, width) {
             Some((intersection, false)) => (intersection, start_points.1),
             Some((intersection, true)) => (start_points.0, intersection),
             None => start_points,
         }
     }
 
-    fn get_adjusted_end_points(line: &PolyLine, i: usize, width: f64) -> (Vector2, Vector2) {
-        let end_points = Self::get_end_points(line, i, width);
+    fn get_adjusted_end_points(&self, line: &PolyLine, i: usize, width: f64) -> (Vector2, Vector2) {
+        let end_points = self.get_end_points(line, i, width);
         if i + 1 == line.points.len() {
             return end_points;
         }
-        match Self::get_connection(line, i, width) {
+        match self.get_connection(line, i, width) {
             Some((intersection, false)) => (intersection, end_points.1),
             Some((intersection, true)) => (end_points.0, intersection),
             None => end_points,
         }
     }
 
-    fn get_connection(line: &PolyLine, i: usize, width: f64) -> Option<(Vector2, bool)> {
-        let start_points = Self::get_start_points(line, i - 1, width);
-        let end_points = Self::get_end_points(line, i, width);
-        let next_start_points = Self::get_start_points(line, i, width);
-        let next_end_points = Self::get_end_points(line, i + 1, width);
+    fn get_connection(&self, line: &PolyLine, i: usize, width: f64) -> Option<(Vector2, bool)> {
+        let start_points = self.get_start_points(line, i - 1, width);
+        let end_points = self.get_end_points(line, i, width);
+        let next_start_points = self.get_start_points(line, i, width);
+        let next_end_points = self.get_end_points(line, i + 1, width);
         let lines = (
             make_line(start_points.0, end_points.0),
             make_line(start_points.1, end_points.1),
@@ -125,124 +281,490 @@ impl ConnectionRenderer {
         }
     }
 
-    fn get_start_points(line: &PolyLine, i: usize, width: f64) -> (Vector2, Vector2) {
+    fn get_start_points(&self, line: &PolyLine, i: usize, width: f64) -> (Vector2, Vector2) {
         if i + 1 == line.points.len() {
             panic!();
         }
-        Self::offset_by_direction(
+        self.offset_by_direction(
             line.points[i],
             (line.points[i + 1] - line.points[i]).normalize() * width,
         )
     }
 
-    fn get_end_points(line: &PolyLine, i: usize, width: f64) -> (Vector2, Vector2) {
+    fn get_end_points(&self, line: &PolyLine, i: usize, width: f64) -> (Vector2, Vector2) {
         if i == 0 {
             panic!();
         }
-        Self::offset_by_direction(
+        self.offset_by_direction(
             line.points[i],
             (line.points[i] - line.points[i - 1]).normalize() * width,
         )
     }
 
-    fn offset_by_direction(point: Vector2, direction: Vector2) -> (Vector2, Vector2) {
+    fn offset_by_direction(&self, point: Vector2, direction: Vector2) -> (Vector2, Vector2) {
+        let (right, left) = self.align.offsets();
         (
-            point + vec2(direction.y, -direction.x),
-            point + vec2(-direction.y, direction.x),
+            point + vec2(direction.y, -direction.x) * right,
+            point + vec2(-direction.y, direction.x) * left,
         )
     }
 }
 
-pub struct TangentRenderer {}
+pub struct TangentRenderer {
+    align: StrokeAlign,
+    closed: bool,
+    // The closed loop, kept between calls so rendering one doesn't allocate.
+    closed_line: RefCell<PolyLine>,
+}
 
-impl TangentRenderer {
-    pub fn new() -> Self {
-        Self {}
+impl Default for TangentRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CurveRenderer for TangentRenderer {
+    fn name(&self) -> &str {
+        "tangent"
     }
 
-    pub fn render(&self, line: &PolyLine, width: f64) -> RenderData {
+    fn render(&self, line: &PolyLine, width: f64) -> RenderData {
         let mut result = RenderData::new();
+        self.render_into(line, width, &mut result);
+        result
+    }
 
-        for i in 1..line.points.len() {
-            result = result.merge(Self::get_segment_render_data(line, i, width));
+    fn render_into(&self, line: &PolyLine, width: f64, out: &mut RenderData) {
+        let closed_line = &mut *self.closed_line.borrow_mut();
+        if self.closed && closed_loop_into(line, closed_line) {
+            let count = closed_line.points.len() - 3;
+            return self.render_range(closed_line, width, 2..count + 2, out);
         }
+        self.render_range(line, width, 1..line.points.len(), out)
+    }
+}
 
-        result
+impl TangentRenderer {
+    /// How far from its point, in widths, a join may reach before the
+    /// miter is dropped.
+    const MITER_LIMIT: f64 = 4.0;
+
+    pub fn new() -> Self {
+        Self {
+            align: StrokeAlign::Center,
+            closed: false,
+            closed_line: RefCell::new(PolyLine::new()),
+        }
+    }
+
+    pub fn with_align(self, align: StrokeAlign) -> Self {
+        Self { align, ..self }
     }
 
-    fn get_segment_render_data(line: &PolyLine, i: usize, width: f64) -> RenderData {
-        let start_points = Self::get_points(line, i - 1, width).unwrap();
-        let end_points = Self::get_points(line, i, width).unwrap();
-        let vertices: Vec<Vertex> = [start_points.0, start_points.1, end_points.0, end_points.1]
-            .map(Vector2::into)
-            .map(Vertex::new_f64)
-            .into_iter()
-            .collect();
-        let indices: Vec<_> = vec![0, 2, 3, 0, 3, 1];
-        RenderData { vertices, indices }
+    pub fn with_closed(self, closed: bool) -> Self {
+        Self { closed, ..self }
     }
 
-    fn get_points(line: &PolyLine, i: usize, width: f64) -> Option<(Vector2, Vector2)> {
+    fn render_range(
+        &self,
+        line: &PolyLine,
+        width: f64,
+        segments: Range<usize>,
+        out: &mut RenderData,
+    ) {
+        for (i, start_v, end_v) in stroke_lengths(line, segments.clone()) {
+            if segments.contains(&i) {
+                self.get_segment_render_data(line, i, width, start_v, end_v, out);
+            }
+        }
+    }
+
+    fn get_segment_render_data(
+        &self,
+        line: &PolyLine,
+        i: usize,
+        width: f64,
+        start_v: f64,
+        end_v: f64,
+        out: &mut RenderData,
+    ) {
+        let start_points = self.get_points(line, i - 1, width);
+        let end_points = self.get_points(line, i, width);
+        let vertices = segment_vertices(start_points, end_points, start_v, end_v);
+        out.extend(vertices, [0, 2, 3, 0, 3, 1]);
+    }
+
+    fn get_points(&self, line: &PolyLine, i: usize, width: f64) -> (Vector2, Vector2) {
         if i == 0 {
-            return Some(Self::get_start_points(line, i, width));
+            return self.get_start_points(line, i, width);
         }
         if i + 1 == line.points.len() {
-            return Some(Self::get_end_points(line, i, width));
+            return self.get_end_points(line, i, width);
         }
-        let start_points = Self::get_start_points(line, i - 1, width);
-        let end_points = Self::get_end_points(line, i, width);
-        let next_start_points = Self::get_start_points(line, i, width);
-        let next_end_points = Self::get_end_points(line, i + 1, width);
-
-        let intersections = (
-            line_intersection(
-                start_points.0,
+        let start_points = self.get_start_points(line, i - 1, width);
+        let end_points = self.get_end_points(line, i, width);
+        let next_start_points = self.get_start_points(line, i, width);
+        let next_end_points = self.get_end_points(line, i + 1, width);
+
+        // Parallel neighbours have no intersection and nearly parallel ones
+        // put it far away, so past the limit the plain offset is used.
+        let miter = |intersection: Option<Vector2>, fallback: Vector2| match intersection {
+            Some(point) if (point - line.points[i]).magnitude() <= Self::MITER_LIMIT * width => {
+                point
+            }
+            _ => fallback,
+        };
+        (
+            miter(
+                line_intersection(
+                    start_points.0,
+                    end_points.0,
+                    next_start_points.0,
+                    next_end_points.0,
+                ),
                 end_points.0,
-                next_start_points.0,
-                next_end_points.0,
             ),
-            line_intersection(
-                start_points.1,
+            miter(
+                line_intersection(
+                    start_points.1,
+                    end_points.1,
+                    next_start_points.1,
+                    next_end_points.1,
+                ),
                 end_points.1,
-                next_start_points.1,
-                next_end_points.1,
             ),
-        );
-
-        match intersections {
-            (Some(intersection1), Some(intersection2)) => Some((intersection1, intersection2)),
-            _ => None,
-        }
+        )
     }
 
-    fn get_start_points(line: &PolyLine, i: usize, width: f64) -> (Vector2, Vector2) {
+    fn get_start_points(&self, line: &PolyLine, i: usize, width: f64) -> (Vector2, Vector2) {
         if i + 1 == line.points.len() {
             panic!();
         }
-        Self::offset_by_direction(
+        self.offset_by_direction(
             line.points[i],
             (line.points[i + 1] - line.points[i]).normalize() * width,
         )
     }
 
-    fn get_end_points(line: &PolyLine, i: usize, width: f64) -> (Vector2, Vector2) {
+    fn get_end_points(&self, line: &PolyLine, i: usize, width: f64) -> (Vector2, Vector2) {
         if i == 0 {
             panic!();
         }
-        Self::offset_by_direction(
+        self.offset_by_direction(
             line.points[i],
             (line.points[i] - line.points[i - 1]).normalize() * width,
         )
     }
 
-    fn offset_by_direction(point: Vector2, direction: Vector2) -> (Vector2, Vector2) {
+    fn offset_by_direction(&self, point: Vector2, direction: Vector2) -> (Vector2, Vector2) {
+        let (right, left) = self.align.offsets();
         (
-            point + vec2(direction.y, -direction.x),
-            point + vec2(-direction.y, direction.x),
+            point + vec2(direction.y, -direction.x) * right,
+            point + vec2(-direction.y, direction.x) * left,
         )
     }
 }
 
+pub struct DotRenderer {
+    spacing: f64,
+    radius: Option<f64>,
+}
+
+impl CurveRenderer for DotRenderer {
+    fn name(&self) -> &str {
+        "dots"
+    }
+
+    fn render(&self, line: &PolyLine, width: f64) -> RenderData {
+        let mut result = RenderData::new();
+        self.render_into(line, width, &mut result);
+        result
+    }
+
+    fn render_into(&self, line: &PolyLine, width: f64, out: &mut RenderData) {
+        append_markers(line, self.spacing, self.radius.unwrap_or(width), out);
+    }
+}
+
+impl DotRenderer {
+    pub fn new(spacing: f64) -> Self {
+        Self {
+            spacing,
+            radius: None,
+        }
+    }
+
+    pub fn with_radius(self, radius: f64) -> Self {
+        Self {
+            radius: Some(radius),
+            ..self
+        }
+    }
+}
+
+pub struct ArrowRenderer<R: CurveRenderer> {
+    inner: R,
+    length: f64,
+    width: f64,
+    start: bool,
+    end: bool,
+    // The line without the arrowheads, kept between calls to reuse it.
+    shaft: RefCell<PolyLine>,
+}
+
+impl<R: CurveRenderer> CurveRenderer for ArrowRenderer<R> {
+    fn name(&self) -> &str {
+        "arrows"
+    }
+
+    fn render(&self, line: &PolyLine, width: f64) -> RenderData {
+        let mut result = RenderData::new();
+        self.render_into(line, width, &mut result);
+        result
+    }
+
+    fn render_into(&self, line: &PolyLine, width: f64, out: &mut RenderData) {
+        let points = &line.points;
+        if points.len() < 2 {
+            return self.inner.render_into(line, width, out);
+        }
+        let cut = |enabled: bool| if enabled { self.length } else { 0.0 };
+        let shaft = &mut *self.shaft.borrow_mut();
+        shorten_into(line, cut(self.start), cut(self.end), shaft);
+        if shaft.points.len() >= 2 {
+            self.inner.render_into(shaft, width, out);
+        }
+        if self.start {
+            self.append_arrowhead(points[0], points[0] - points[1], out);
+        }
+        if self.end {
+            let last = points.len() - 1;
+            self.append_arrowhead(points[last], points[last] - points[last - 1], out);
+        }
+    }
+}
+
+impl<R: CurveRenderer> ArrowRenderer<R> {
+    pub fn new(inner: R, length: f64, width: f64) -> Self {
+        Self {
+            inner,
+            length,
+            width,
+            start: false,
+            end: true,
+            shaft: RefCell::new(PolyLine::new()),
+        }
+    }
+
+    pub fn with_start(self, start: bool) -> Self {
+        Self { start, ..self }
+    }
+
+    pub fn with_end(self, end: bool) -> Self {
+        Self { end, ..self }
+    }
+
+    fn append_arrowhead(&self, tip: Vector2, direction: Vector2, out: &mut RenderData) {
+        if direction.magnitude2() == 0.0 {
+            return;
+        }
+        let direction = direction.normalize();
+        let base = tip - direction * self.length;
+        let side = vec2(-direction.y, direction.x) * (self.width / 2.0);
+        out.extend(
+            [tip, base + side, base - side].map(|point| Vertex::new_f64(point.into())),
+            [0, 1, 2],
+        );
+    }
+}
+
+/// Lines `line_width` wide across the box from `min` to `max`, at every
+/// multiple of `spacing` inside it.
+pub fn grid_render_data(min: Vector2, max: Vector2, spacing: f64, line_width: f64) -> RenderData {
+    let renderer = ConnectionRenderer::new();
+    let vertical = multiples(min.x, max.x, spacing).map(|x| [vec2(x, min.y), vec2(x, max.y)]);
+    let horizontal = multiples(min.y, max.y, spacing).map(|y| [vec2(min.x, y), vec2(max.x, y)]);
+    let mut result = RenderData::new();
+    for points in vertical.chain(horizontal) {
+        renderer.render_into(&PolyLine::from_iter(points), line_width / 2.0, &mut result);
+    }
+    result
+}
+
+/// The x and y axes, moved to the nearest edge when the box doesn't contain
+/// them, with ticks at every multiple of `minor` and ticks twice as long at
+/// every multiple of `major`.
+pub fn axes_render_data(
+    min: Vector2,
+    max: Vector2,
+    major: f64,
+    minor: f64,
+    line_width: f64,
+) -> RenderData {
+    let renderer = ConnectionRenderer::new();
+    let origin = vec2(0.0f64.clamp(min.x, max.x), 0.0f64.clamp(min.y, max.y));
+    let tick_length = |value: f64| {
+        let on_major = major > 0.0 && ((value / major).round() * major - value).abs() < minor / 2.0;
+        if on_major {
+            minor
+        } else {
+            minor / 2.0
+        }
+    };
+    let x_ticks = multiples(min.x, max.x, minor).map(|x| {
+        let half = vec2(0.0, tick_length(x) / 2.0);
+        [vec2(x, origin.y) - half, vec2(x, origin.y) + half]
+    });
+    let y_ticks = multiples(min.y, max.y, minor).map(|y| {
+        let half = vec2(tick_length(y) / 2.0, 0.0);
+        [vec2(origin.x, y) - half, vec2(origin.x, y) + half]
+    });
+    let axes = [
+        [vec2(min.x, origin.y), vec2(max.x, origin.y)],
+        [vec2(origin.x, min.y), vec2(origin.x, max.y)],
+    ];
+    let mut result = RenderData::new();
+    for points in axes.into_iter().chain(x_ticks).chain(y_ticks) {
+        renderer.render_into(&PolyLine::from_iter(points), line_width / 2.0, &mut result);
+    }
+    result
+}
+
+/// Dots of `radius` at the points `resample` gives, so they are exactly
+/// `spacing` apart along the line.
+pub fn marker_render_data(line: &PolyLine, spacing: f64, radius: f64) -> RenderData {
+    let mut result = RenderData::new();
+    append_markers(line, spacing, radius, &mut result);
+    result
+}
+
+fn append_markers(line: &PolyLine, spacing: f64, radius: f64, out: &mut RenderData) {
+    for point in line.resampled(spacing) {
+        append_circle_fan(point, radius, out);
+    }
+}
+
+// Multiples of `step` from `from` to `to`, inclusive.
+fn multiples(from: f64, to: f64, step: f64) -> impl Iterator<Item = f64> {
+    let (first, last) = if step > 0.0 {
+        ((from / step).ceil() as i64, (to / step).floor() as i64)
+    } else {
+        (1, 0)
+    };
+    (first..=last).map(move |i| i as f64 * step)
+}
+
+// Writes the part of the line from `from_start` along it to `from_end`
+// before its end to `out`, which is left empty when nothing remains.
+fn shorten_into(line: &PolyLine, from_start: f64, from_end: f64, out: &mut PolyLine) {
+    out.points.clear();
+    let total: f64 = line.segments().map(|(a, b)| (b - a).magnitude()).sum();
+    let (first, last) = (from_start, total - from_end);
+    if first >= last {
+        return;
+    }
+    out.points.push(point_at_distance(line, first));
+    let mut length = 0.0;
+    for (i, &point) in line.points.iter().enumerate() {
+        if i > 0 {
+            length += (point - line.points[i - 1]).magnitude();
+        }
+        if length > first && length < last {
+            out.points.push(point);
+        }
+    }
+    out.points.push(point_at_distance(line, last));
+}
+
+// The point `distance` along a line of at least two points, extrapolated
+// from the end segments beyond its ends.
+fn point_at_distance(line: &PolyLine, distance: f64) -> Vector2 {
+    let points = &line.points;
+    let mut travelled = 0.0;
+    for i in 1..points.len() {
+        let span = (points[i] - points[i - 1]).magnitude();
+        if travelled + span >= distance || i + 1 == points.len() {
+            let t = if span > 0.0 {
+                (distance - travelled) / span
+            } else {
+                0.0
+            };
+            return super::Bezier::lerp(points[i - 1], points[i], t);
+        }
+        travelled += span;
+    }
+    points[0]
+}
+
+const CIRCLE_FAN_SEGMENTS: u32 = 12;
+
+fn append_circle_fan(center: Vector2, radius: f64, out: &mut RenderData) {
+    let vertices = std::iter::once(center)
+        .chain((0..CIRCLE_FAN_SEGMENTS).map(|i| {
+            let angle = std::f64::consts::TAU * i as f64 / CIRCLE_FAN_SEGMENTS as f64;
+            center + vec2(angle.cos(), angle.sin()) * radius
+        }))
+        .map(|point| Vertex::new_f64(point.into()));
+    let indices =
+        (0..CIRCLE_FAN_SEGMENTS).flat_map(|i| [0, i + 1, (i + 1) % CIRCLE_FAN_SEGMENTS + 1]);
+    out.extend(vertices, indices);
+}
+
+// Writes the line to `out` with the point before the start prepended and
+// the two after the end appended, or gives false when it can't be closed.
+fn closed_loop_into(line: &PolyLine, out: &mut PolyLine) -> bool {
+    let mut points = line.points.as_slice();
+    if points.len() > 1 && points.first() == points.last() {
+        points = &points[..points.len() - 1];
+    }
+    if points.len() < 3 {
+        return false;
+    }
+    let last = points[points.len() - 1];
+    out.points.clear();
+    out.points.extend(
+        std::iter::once(last)
+            .chain(points.iter().copied())
+            .chain(points.iter().copied().take(2)),
+    );
+    true
+}
+
+// Yields every point from the start of the first segment to the end of the
+// last one with the V coordinates of the segment ending there, normalized
+// over the whole range.
+fn stroke_lengths(
+    line: &PolyLine,
+    segments: Range<usize>,
+) -> impl Iterator<Item = (usize, f64, f64)> + '_ {
+    let segment_length = |i: usize| (line.points[i] - line.points[i - 1]).magnitude();
+    let total: f64 = segments.clone().map(segment_length).sum();
+    let scale = if total > 0.0 { 1.0 / total } else { 0.0 };
+    let first = segments.start.saturating_sub(1);
+    (first..segments.end.max(first)).scan(0.0, move |length, i| {
+        let start_v = *length * scale;
+        if i > first {
+            *length += segment_length(i);
+        }
+        Some((i, start_v, *length * scale))
+    })
+}
+
+fn segment_vertices(
+    start_points: (Vector2, Vector2),
+    end_points: (Vector2, Vector2),
+    start_v: f64,
+    end_v: f64,
+) -> [Vertex; 4] {
+    [
+        (start_points.0, [0.0, start_v as f32]),
+        (start_points.1, [1.0, start_v as f32]),
+        (end_points.0, [0.0, end_v as f32]),
+        (end_points.1, [1.0, end_v as f32]),
+    ]
+    .map(|(point, uv)| Vertex::new_f64(point.into()).with_uv(uv))
+}
+
 fn make_line(start: Vector2, end: Vector2) -> geo::Line<f64> {
     geo::Line {
         start: geo::Coord {
@@ -265,3 +787,56 @@ fn line_intersection(p1: Vector2, p2: Vector2, p3: Vector2, p4: Vector2) -> Opti
         Some(vec2(x_numerator / denominator, y_numerator / denominator))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::spline::Spline;
+    use crate::curve::Bezier;
+
+    fn positions(data: &RenderData) -> impl Iterator<Item = [f32; 2]> + '_ {
+        data.vertices.iter().map(|&vertex| {
+            let [x, y, ..]: [f32; 8] = bytemuck::cast(vertex);
+            [x, y]
+        })
+    }
+
+    #[test]
+    fn tangent_stroke_of_a_straight_line_stays_on_it() {
+        let line = PolyLine {
+            points: (0..5).map(|i| vec2(i as f64 * 0.1, 0.25)).collect(),
+        };
+        let data = TangentRenderer::new().render(&line, 0.01);
+        assert_eq!(data.index_count(), 4 * 6);
+        assert!(positions(&data).all(|[_, y]| (y - 0.25).abs() <= 0.011));
+    }
+
+    // The way `State` draws curves from `set_curves`: each one subdivided
+    // into the same line and appended to the same data.
+    #[test]
+    fn straight_curves_render_into_a_reused_line() {
+        let curves = [
+            Bezier::new(vec2(-0.5, 0.0), vec2(0.0, 0.0), vec2(0.5, 0.0)),
+            Bezier::new(vec2(0.0, -0.5), vec2(0.0, 0.1), vec2(0.0, 0.5)),
+            Bezier::new(vec2(-0.5, -0.5), vec2(0.0, 0.0), vec2(0.5, 0.5)),
+        ];
+        let renderer = TangentRenderer::new();
+        let mut line = PolyLine::new();
+        let mut data = RenderData::new();
+        for curve in &curves {
+            curve.subdivide_into(30, &mut line.points);
+            renderer.render_into(&line, 0.01, &mut data);
+        }
+        data.validate();
+        assert_eq!(data.index_count(), curves.len() * 29 * 6);
+        assert!(positions(&data).all(|[x, y]| x.abs() <= 0.51 && y.abs() <= 0.51));
+    }
+
+    #[test]
+    fn closed_tangent_stroke_of_a_polygon_stays_near_it() {
+        let square = Spline::regular_polygon(vec2(0.0, 0.0), 0.5, 4).subdivide(8);
+        let data = TangentRenderer::new().with_closed(true).render(&square, 0.01);
+        assert!(data.index_count() > 0);
+        assert!(positions(&data).all(|[x, y]| x.abs() <= 0.52 && y.abs() <= 0.52));
+    }
+}
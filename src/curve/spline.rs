@@ -0,0 +1,580 @@
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use cgmath::{BaseFloat, InnerSpace};
+
+use super::cubic_roots;
+use super::vec2;
+use super::Bezier;
+use super::CubicBezier;
+use super::FillRule;
+use super::PolyLine;
+use super::Vector2;
+
+pub struct Spline<T: BaseFloat = f64> {
+    pub segments: Vec<CubicBezier<T>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Continuity {
+    Discontinuous,
+    C0,
+    G1,
+    C1,
+}
+
+impl<T: BaseFloat> Spline<T> {
+    pub fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn subdivide(&self, count: usize) -> PolyLine<T> {
+        let mut points = Vec::new();
+        for segment in &self.segments {
+            let segment_points = segment.subdivide(count).points;
+            let skip = if points.is_empty() { 0 } else { 1 };
+            points.extend(segment_points.into_iter().skip(skip));
+        }
+        PolyLine { points }
+    }
+}
+
+impl Spline {
+    const CONTINUITY_TOLERANCE: f64 = 1e-9;
+
+    pub fn continuity(&self) -> Vec<Continuity> {
+        self.continuity_with_tolerance(Self::CONTINUITY_TOLERANCE)
+    }
+
+    /// `tolerance` bounds both the gap between joined endpoints and the
+    /// difference between tangents (unit tangents for G1).
+    pub fn continuity_with_tolerance(&self, tolerance: f64) -> Vec<Continuity> {
+        self.segments
+            .windows(2)
+            .map(|pair| {
+                if (pair[1].start - pair[0].end).magnitude() > tolerance {
+                    return Continuity::Discontinuous;
+                }
+                let incoming = pair[0].derivative(1.0);
+                let outgoing = pair[1].derivative(0.0);
+                if (outgoing - incoming).magnitude() <= tolerance {
+                    Continuity::C1
+                } else if incoming.magnitude2() > 0.0
+                    && outgoing.magnitude2() > 0.0
+                    && (outgoing.normalize() - incoming.normalize()).magnitude() <= tolerance
+                {
+                    Continuity::G1
+                } else {
+                    Continuity::C0
+                }
+            })
+            .collect()
+    }
+
+    /// Rotates the two handles at every connected join onto their bisector so
+    /// the tangent directions agree. Knots and handle lengths are kept, so a
+    /// handle moves by at most half the angle of the kink it straightens;
+    /// joins whose handles point in exactly opposite directions are left as is.
+    pub fn smooth_g1(&mut self) {
+        for i in 1..self.segments.len() {
+            let knot = self.segments[i - 1].end;
+            if (self.segments[i].start - knot).magnitude() > Self::CONTINUITY_TOLERANCE {
+                continue;
+            }
+            let incoming = knot - self.segments[i - 1].control2;
+            let outgoing = self.segments[i].control1 - knot;
+            if incoming.magnitude2() == 0.0 || outgoing.magnitude2() == 0.0 {
+                continue;
+            }
+            let bisector = incoming.normalize() + outgoing.normalize();
+            if bisector.magnitude2() == 0.0 {
+                continue;
+            }
+            let direction = bisector.normalize();
+            self.segments[i - 1].control2 = knot - direction * incoming.magnitude();
+            self.segments[i].control1 = knot + direction * outgoing.magnitude();
+        }
+    }
+
+    /// Converts a B-spline with a uniform knot vector of degree 1 to 3 into one
+    /// cubic segment per knot span.
+    pub fn from_bspline(control: &[Vector2], degree: usize) -> Self {
+        let knots: Vec<f64> = (0..control.len() + degree + 1).map(|i| i as f64).collect();
+        Self::from_bspline_knots(control, degree, &knots)
+    }
+
+    /// Like [`Spline::from_bspline`], but with the end knots repeated so the
+    /// curve starts and ends at the first and last control points.
+    pub fn from_clamped_bspline(control: &[Vector2], degree: usize) -> Self {
+        let spans = control.len().saturating_sub(degree);
+        let knots: Vec<f64> = (0..control.len() + degree + 1)
+            .map(|i| i.saturating_sub(degree).min(spans) as f64)
+            .collect();
+        Self::from_bspline_knots(control, degree, &knots)
+    }
+
+    fn from_bspline_knots(control: &[Vector2], degree: usize, knots: &[f64]) -> Self {
+        assert!(
+            (1..=3).contains(&degree),
+            "only degrees 1 to 3 are supported"
+        );
+        if control.len() <= degree {
+            return Self::new();
+        }
+        let segments = (degree..control.len())
+            .filter(|&span| knots[span] < knots[span + 1])
+            .map(|span| {
+                let (from, to) = (knots[span], knots[span + 1]);
+                // The k-th Bezier control point of a span is the blossom with k
+                // arguments at its end and the rest at its start.
+                let points: Vec<Vector2> = (0..=degree)
+                    .map(|k| {
+                        let arguments: Vec<f64> =
+                            (0..degree).map(|i| if i < k { to } else { from }).collect();
+                        blossom(control, degree, knots, span, &arguments)
+                    })
+                    .collect();
+                match degree {
+                    1 => CubicBezier::line(points[0], points[1]),
+                    2 => CubicBezier::new(
+                        points[0],
+                        points[0] + (points[1] - points[0]) * (2.0 / 3.0),
+                        points[2] + (points[1] - points[2]) * (2.0 / 3.0),
+                        points[2],
+                    ),
+                    _ => CubicBezier::new(points[0], points[1], points[2], points[3]),
+                }
+            })
+            .collect();
+        Self { segments }
+    }
+
+    /// Whether `p` lies inside the closed shape bounded by the segments, from
+    /// the crossings of a ray towards +x with each curve found analytically.
+    pub fn contains(&self, p: Vector2, rule: FillRule) -> bool {
+        let mut winding = 0;
+        for segment in &self.segments {
+            let [y0, y1, y2, y3] = [
+                segment.start.y,
+                segment.control1.y,
+                segment.control2.y,
+                segment.end.y,
+            ];
+            let roots = cubic_roots(
+                -y0 + 3.0 * y1 - 3.0 * y2 + y3,
+                3.0 * y0 - 6.0 * y1 + 3.0 * y2,
+                3.0 * (y1 - y0),
+                y0 - p.y,
+            );
+            // Half-open so a crossing at a shared endpoint counts once.
+            for t in roots.into_iter().filter(|t| (0.0..1.0).contains(t)) {
+                let dy = segment.derivative(t).y;
+                if segment.eval(t).x > p.x && dy != 0.0 {
+                    winding += if dy > 0.0 { 1 } else { -1 };
+                }
+            }
+        }
+        match rule {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+
+    const FIT_REPARAMETERIZE_ITERATIONS: usize = 4;
+
+    /// Approximates `points` with cubics using Schneider's algorithm: a cubic
+    /// is fitted by least squares and, if it strays further than `max_error`
+    /// from any point, the points are split at the worst one and both halves
+    /// are fitted again with a shared tangent at the split.
+    pub fn fit(points: &[Vector2], max_error: f64) -> Self {
+        let mut points = points.to_vec();
+        points.dedup();
+        let mut spline = Self::new();
+        if points.len() < 2 {
+            return spline;
+        }
+        let last = points.len() - 1;
+        let start_tangent = (points[1] - points[0]).normalize();
+        let end_tangent = (points[last - 1] - points[last]).normalize();
+        spline.fit_cubic(&points, start_tangent, end_tangent, max_error);
+        spline
+    }
+
+    fn fit_cubic(
+        &mut self,
+        points: &[Vector2],
+        start_tangent: Vector2,
+        end_tangent: Vector2,
+        max_error: f64,
+    ) {
+        let (first, last) = (points[0], points[points.len() - 1]);
+        if points.len() == 2 {
+            let handle = (last - first).magnitude() / 3.0;
+            self.segments.push(CubicBezier::new(
+                first,
+                first + start_tangent * handle,
+                last + end_tangent * handle,
+                last,
+            ));
+            return;
+        }
+
+        let mut parameters = chord_length_parameters(points);
+        let mut curve = fit_with_tangents(points, &parameters, start_tangent, end_tangent);
+        let (mut error, mut split) = max_fit_error(points, &parameters, &curve);
+        if error <= max_error {
+            self.segments.push(curve);
+            return;
+        }
+        if error <= max_error * 4.0 {
+            for _ in 0..Self::FIT_REPARAMETERIZE_ITERATIONS {
+                parameters = parameters
+                    .iter()
+                    .zip(points)
+                    .map(|(&t, &point)| newton_step(&curve, point, t))
+                    .collect();
+                curve = fit_with_tangents(points, &parameters, start_tangent, end_tangent);
+                (error, split) = max_fit_error(points, &parameters, &curve);
+                if error <= max_error {
+                    self.segments.push(curve);
+                    return;
+                }
+            }
+        }
+
+        let center = points[split - 1] - points[split + 1];
+        let center_tangent = if center.magnitude2() > 0.0 {
+            center.normalize()
+        } else {
+            vec2(start_tangent.y, -start_tangent.x)
+        };
+        self.fit_cubic(&points[..=split], start_tangent, center_tangent, max_error);
+        self.fit_cubic(&points[split..], -center_tangent, end_tangent, max_error);
+    }
+
+    /// A uniform Catmull-Rom spline through `points`, with the end points
+    /// repeated as their own outer neighbors.
+    pub fn catmull_rom(points: &[Vector2]) -> Self {
+        Self {
+            segments: Self::catmull_rom_segments(points).collect(),
+        }
+    }
+
+    /// The segments of `catmull_rom`, computed as they are consumed.
+    pub fn catmull_rom_segments(points: &[Vector2]) -> impl Iterator<Item = CubicBezier> + '_ {
+        let point = move |i: isize| points[i.clamp(0, points.len() as isize - 1) as usize];
+        (0..points.len().saturating_sub(1) as isize).map(move |i| {
+            CubicBezier::new(
+                point(i),
+                point(i) + (point(i + 1) - point(i - 1)) / 6.0,
+                point(i + 1) - (point(i + 2) - point(i)) / 6.0,
+                point(i + 1),
+            )
+        })
+    }
+
+    const FILLET_ITERATIONS: usize = 64;
+
+    /// Rounds the corner where `a` ends and `b` starts: both curves are cut
+    /// back by the setback of a circular fillet of `radius` between their
+    /// tangents at the corner, and the gap is bridged by an arc-like cubic
+    /// tangent to both cut ends. The setback is clamped to the shorter chord,
+    /// which shrinks the radius when it doesn't fit.
+    pub fn fillet(a: &Bezier, b: &Bezier, radius: f64) -> Self {
+        let corner = a.end;
+        let (incoming, outgoing) = (a.frame_at(1.0).1, b.frame_at(0.0).1);
+        let turn = incoming.angle(outgoing).0.abs();
+        let setback = (radius.max(0.0) * (turn / 2.0).tan())
+            .min((a.end - a.start).magnitude())
+            .min((b.end - b.start).magnitude());
+        if setback.is_nan() || setback <= 0.0 {
+            return Self {
+                segments: vec![(*a).into(), (*b).into()],
+            };
+        }
+
+        let t_a = Self::parameter_at_distance(a, corner, setback, true);
+        let t_b = Self::parameter_at_distance(b, corner, setback, false);
+        let (from, from_tangent, _) = a.frame_at(t_a);
+        let (to, to_tangent, _) = b.frame_at(t_b);
+        let chord = (to - from).magnitude();
+        let sweep = from_tangent.angle(to_tangent).0.abs();
+        let handle = if sweep > 0.0 {
+            chord * 4.0 / 3.0 * (sweep / 4.0).tan() / (2.0 * (sweep / 2.0).sin())
+        } else {
+            chord / 3.0
+        };
+
+        let (head, tail) = (a.trim(0.0, t_a), b.trim(t_b, 1.0));
+        let fillet = CubicBezier::new(
+            from,
+            from + from_tangent * handle,
+            to - to_tangent * handle,
+            to,
+        );
+        // A setback clamped to a whole chord leaves nothing of that curve.
+        let is_point = |curve: &Bezier| curve.start == curve.middle && curve.middle == curve.end;
+        Self {
+            segments: [
+                (!is_point(&head)).then(|| head.into()),
+                Some(fillet),
+                (!is_point(&tail)).then(|| tail.into()),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+        }
+    }
+
+    // Bisects for the parameter at `distance` from `corner`, counting from the
+    // end of the curve if `from_end` is set and from its start otherwise.
+    fn parameter_at_distance(
+        curve: &Bezier,
+        corner: Vector2,
+        distance: f64,
+        from_end: bool,
+    ) -> f64 {
+        let (mut near, mut far) = if from_end { (1.0, 0.0) } else { (0.0, 1.0) };
+        for _ in 0..Self::FILLET_ITERATIONS {
+            let middle = (near + far) / 2.0;
+            if (curve.eval(middle) - corner).magnitude() < distance {
+                near = middle;
+            } else {
+                far = middle;
+            }
+        }
+        (near + far) / 2.0
+    }
+
+    pub fn arc(center: Vector2, radius: f64, start_angle: f64, end_angle: f64) -> Self {
+        let span = end_angle - start_angle;
+        let count = ((span.abs() / FRAC_PI_2).ceil() as usize).max(1);
+        let step = span / count as f64;
+        let handle = 4.0 / 3.0 * (step / 4.0).tan() * radius;
+
+        let point = |angle: f64| center + vec2(angle.cos(), angle.sin()) * radius;
+        let tangent = |angle: f64| vec2(-angle.sin(), angle.cos()) * handle;
+
+        Self {
+            segments: (0..count)
+                .map(|i| {
+                    let from = start_angle + step * i as f64;
+                    let to = from + step;
+                    CubicBezier::new(
+                        point(from),
+                        point(from) + tangent(from),
+                        point(to) - tangent(to),
+                        point(to),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn circle(center: Vector2, radius: f64) -> Self {
+        Self::arc(center, radius, 0.0, TAU)
+    }
+
+    pub fn ellipse(center: Vector2, rx: f64, ry: f64) -> Self {
+        let scale = |p: Vector2| center + vec2(p.x * rx, p.y * ry);
+        Self {
+            segments: Self::arc(vec2(0.0, 0.0), 1.0, 0.0, TAU)
+                .segments
+                .into_iter()
+                .map(|segment| {
+                    CubicBezier::new(
+                        scale(segment.start),
+                        scale(segment.control1),
+                        scale(segment.control2),
+                        scale(segment.end),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// A closed polygon with its first vertex at -y from `center` (the top in
+    /// y-down coordinates) and the rest following at increasing angles.
+    pub fn regular_polygon(center: Vector2, radius: f64, sides: usize) -> Self {
+        Self::closed_polygon(center, &vec![radius; sides])
+    }
+
+    /// Like [`Spline::regular_polygon`], alternating between the outer and
+    /// inner radius, starting with an outer tip.
+    pub fn star(center: Vector2, outer_r: f64, inner_r: f64, points: usize) -> Self {
+        let radii: Vec<f64> = (0..points * 2)
+            .map(|i| if i % 2 == 0 { outer_r } else { inner_r })
+            .collect();
+        Self::closed_polygon(center, &radii)
+    }
+
+    fn closed_polygon(center: Vector2, radii: &[f64]) -> Self {
+        let vertex = |i: usize| {
+            let angle = -FRAC_PI_2 + TAU * i as f64 / radii.len() as f64;
+            center + vec2(angle.cos(), angle.sin()) * radii[i % radii.len()]
+        };
+        Self {
+            segments: (0..radii.len())
+                .map(|i| CubicBezier::line(vertex(i), vertex(i + 1)))
+                .collect(),
+        }
+    }
+
+    /// Rotates every control point counterclockwise by `angle` around `center`.
+    pub fn rotated(&self, center: Vector2, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        let rotate = |p: Vector2| {
+            let d = p - center;
+            center + vec2(d.x * cos - d.y * sin, d.x * sin + d.y * cos)
+        };
+        Self {
+            segments: self
+                .segments
+                .iter()
+                .map(|segment| {
+                    CubicBezier::new(
+                        rotate(segment.start),
+                        rotate(segment.control1),
+                        rotate(segment.control2),
+                        rotate(segment.end),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn rounded_rect(min: Vector2, max: Vector2, corner_radius: f64) -> Self {
+        let r = corner_radius
+            .min((max.x - min.x) / 2.0)
+            .min((max.y - min.y) / 2.0)
+            .max(0.0);
+        let corners = [
+            (vec2(max.x - r, min.y + r), -FRAC_PI_2),
+            (vec2(max.x - r, max.y - r), 0.0),
+            (vec2(min.x + r, max.y - r), FRAC_PI_2),
+            (vec2(min.x + r, min.y + r), PI),
+        ];
+
+        let mut segments = Vec::new();
+        for (i, &(center, angle)) in corners.iter().enumerate() {
+            let (previous_center, previous_angle) =
+                corners[(i + corners.len() - 1) % corners.len()];
+            let previous_end = previous_angle + FRAC_PI_2;
+            let edge_start = previous_center + vec2(previous_end.cos(), previous_end.sin()) * r;
+            let edge_end = center + vec2(angle.cos(), angle.sin()) * r;
+            if edge_start != edge_end {
+                segments.push(CubicBezier::line(edge_start, edge_end));
+            }
+            if r > 0.0 {
+                segments.extend(Self::arc(center, r, angle, angle + FRAC_PI_2).segments);
+            }
+        }
+        Self { segments }
+    }
+}
+
+impl<T: BaseFloat> Default for Spline<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn blossom(
+    control: &[Vector2],
+    degree: usize,
+    knots: &[f64],
+    span: usize,
+    arguments: &[f64],
+) -> Vector2 {
+    let mut points = control[span - degree..=span].to_vec();
+    for (r, &t) in (1..=degree).zip(arguments) {
+        for j in (r..=degree).rev() {
+            let i = span - degree + j;
+            let alpha = (t - knots[i]) / (knots[i + degree + 1 - r] - knots[i]);
+            points[j] = points[j - 1] * (1.0 - alpha) + points[j] * alpha;
+        }
+    }
+    points[degree]
+}
+
+fn chord_length_parameters(points: &[Vector2]) -> Vec<f64> {
+    let lengths = PolyLine {
+        points: points.to_vec(),
+    }
+    .cumulative_lengths();
+    let total = lengths[lengths.len() - 1];
+    lengths.iter().map(|length| length / total).collect()
+}
+
+// Least-squares handle lengths along fixed tangent directions.
+fn fit_with_tangents(
+    points: &[Vector2],
+    parameters: &[f64],
+    start_tangent: Vector2,
+    end_tangent: Vector2,
+) -> CubicBezier {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let (mut c00, mut c01, mut c11, mut x0, mut x1) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for (&point, &t) in points.iter().zip(parameters) {
+        let s = 1.0 - t;
+        let a0 = start_tangent * (3.0 * s * s * t);
+        let a1 = end_tangent * (3.0 * s * t * t);
+        let rest = point - CubicBezier::new(first, first, last, last).eval(t);
+        c00 += a0.dot(a0);
+        c01 += a0.dot(a1);
+        c11 += a1.dot(a1);
+        x0 += a0.dot(rest);
+        x1 += a1.dot(rest);
+    }
+    let determinant = c00 * c11 - c01 * c01;
+    let chord = (last - first).magnitude();
+    let (alpha0, alpha1) = if determinant.abs() > f64::EPSILON {
+        (
+            (x0 * c11 - x1 * c01) / determinant,
+            (c00 * x1 - c01 * x0) / determinant,
+        )
+    } else {
+        (0.0, 0.0)
+    };
+    let minimum = chord * 1e-6;
+    let (alpha0, alpha1) = if alpha0 < minimum || alpha1 < minimum {
+        (chord / 3.0, chord / 3.0)
+    } else {
+        (alpha0, alpha1)
+    };
+    CubicBezier::new(
+        first,
+        first + start_tangent * alpha0,
+        last + end_tangent * alpha1,
+        last,
+    )
+}
+
+fn max_fit_error(points: &[Vector2], parameters: &[f64], curve: &CubicBezier) -> (f64, usize) {
+    let mut worst = (0.0, points.len() / 2);
+    for i in 1..points.len() - 1 {
+        let error = (curve.eval(parameters[i]) - points[i]).magnitude();
+        if error > worst.0 {
+            worst = (error, i);
+        }
+    }
+    worst
+}
+
+fn newton_step(curve: &CubicBezier, point: Vector2, t: f64) -> f64 {
+    let difference = curve.eval(t) - point;
+    let first = curve.derivative(t);
+    let second = super::Bezier::new(
+        curve.control1 - curve.start,
+        curve.control2 - curve.control1,
+        curve.end - curve.control2,
+    )
+    .derivative(t)
+        * 3.0;
+    let denominator = first.magnitude2() + difference.dot(second);
+    if denominator.abs() <= f64::EPSILON {
+        return t;
+    }
+    (t - difference.dot(first) / denominator).clamp(0.0, 1.0)
+}
@@ -0,0 +1,37 @@
+struct Curve {
+    start: vec2<f32>,
+    middle: vec2<f32>,
+    end: vec2<f32>,
+    width: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> curve: Curve;
+
+struct VertexInput {
+    @location(0) t: f32,
+    @location(1) side: f32,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    let a = mix(curve.start, curve.middle, model.t);
+    let b = mix(curve.middle, curve.end, model.t);
+    var direction = b - a;
+    if dot(direction, direction) == 0.0 {
+        direction = curve.end - curve.start;
+    }
+    var normal = vec2<f32>(0.0, 0.0);
+    if dot(direction, direction) > 0.0 {
+        let tangent = normalize(direction);
+        normal = vec2<f32>(-tangent.y, tangent.x);
+    }
+
+    var out: VertexOutput;
+    out.color = srgb_to_linear(vec3<f32>(1.0, 1.0, 1.0));
+    out.clip_position = vec4<f32>(mix(a, b, model.t) + normal * model.side * curve.width, 0.0, 1.0);
+    out.uv = vec2<f32>((model.side + 1.0) / 2.0, model.t);
+    return out;
+}
@@ -1,9 +1,13 @@
+mod config;
 pub mod curve;
+pub mod scene;
 mod state;
 pub mod vertex;
 
-use std::time::SystemTime;
+use std::{path::Path, time::SystemTime};
 
+pub use config::RunConfig;
+use scene::{Scene, SceneError};
 use state::State;
 pub use vertex::Vertex;
 
@@ -15,51 +19,65 @@ use winit::{
 };
 
 pub async fn run() {
+    run_with(RunConfig::default()).await;
+}
+
+pub async fn run_from_file(path: impl AsRef<Path>) -> Result<(), SceneError> {
+    let scene = Scene::load(path)?;
+    run_with(RunConfig {
+        scene: Some(scene),
+        ..Default::default()
+    })
+    .await;
+    Ok(())
+}
+
+pub async fn run_with(config: RunConfig) {
     env_logger::init();
     let event_loop = EventLoop::new().unwrap();
     event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
 
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    let window = WindowBuilder::new()
+        .with_title(&config.title)
+        .with_inner_size(winit::dpi::PhysicalSize::new(config.width, config.height))
+        .build(&event_loop)
+        .unwrap();
 
     let window_ref = &window;
 
-    let mut state = State::new(&window).await;
+    let mut state = State::new(&window, config).await;
 
     let start_time = SystemTime::now();
 
-    let _ = event_loop.run(move |mut event, control_flow| match event {
+    let _ = event_loop.run(move |event, control_flow| match event {
         Event::WindowEvent {
-            ref mut event,
+            ref event,
             window_id,
-        } if window_id == state.window().id() => {
-            if !state.input(event) {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        event:
-                            KeyEvent {
-                                physical_key: PhysicalKey::Code(KeyCode::Escape),
-                                ..
-                            },
+        } if window_id == state.window().id() && !state.input(event) => match event {
+            WindowEvent::CloseRequested
+            | WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::Escape),
                         ..
-                    } => control_flow.exit(),
-                    WindowEvent::Resized(physical_size) => {
-                        state.resize(*physical_size);
-                    }
-                    WindowEvent::RedrawRequested => {
-                        state.update(start_time.elapsed().unwrap());
-                        match state.render() {
-                            Ok(_) => {}
-                            Err(wgpu::SurfaceError::Lost) => {} /*state.resize(state.size)*/,
-                            Err(wgpu::SurfaceError::OutOfMemory) => control_flow.exit(),
-                            Err(e) => eprintln!("{:?}", e),
-                        };
-                        window_ref.request_redraw();
-                    }
-                    _ => {}
-                }
+                    },
+                ..
+            } => control_flow.exit(),
+            WindowEvent::Resized(physical_size) => {
+                state.resize(*physical_size);
+            }
+            WindowEvent::RedrawRequested => {
+                state.update(start_time.elapsed().unwrap());
+                match state.render() {
+                    Ok(_) => {}
+                    Err(wgpu::SurfaceError::Lost) => state.resize(state.window().inner_size()),
+                    Err(wgpu::SurfaceError::OutOfMemory) => control_flow.exit(),
+                    Err(e) => eprintln!("{:?}", e),
+                };
+                window_ref.request_redraw();
             }
-        }
+            _ => {}
+        },
         _ => {}
     });
 }
@@ -1,5 +1,13 @@
-use bezier::run;
+use bezier::{run, run_from_file};
 
 fn main() {
-    pollster::block_on(run());
+    match std::env::args().nth(1) {
+        Some(path) => {
+            if let Err(error) = pollster::block_on(run_from_file(path)) {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+        None => pollster::block_on(run()),
+    }
 }
@@ -0,0 +1,117 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::curve::Bezier;
+
+/// Curves to display, as read from JSON like
+/// `{"curves": [{"points": [[-0.5, 0.0], [0.0, 0.5], [0.5, 0.0]], "width": 4.0}]}`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scene {
+    pub curves: Vec<SceneCurve>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneCurve {
+    /// Start, middle and end control points.
+    pub points: [[f64; 2]; 3],
+    /// Stroke width in pixels, the window's current one when missing.
+    #[serde(default)]
+    pub width: Option<f32>,
+    /// sRGB color from 0 to 1.
+    #[serde(default)]
+    pub color: Option<[f32; 3]>,
+    /// The window's current renderer when missing.
+    #[serde(default)]
+    pub renderer: Option<RendererKind>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RendererKind {
+    Tangent,
+    Connection,
+    Dots,
+    Arrows,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl Scene {
+    pub fn from_json(json: &str) -> Result<Scene, SceneError> {
+        serde_json::from_str(json).map_err(SceneError::Json)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Scene, SceneError> {
+        Self::from_json(&std::fs::read_to_string(path).map_err(SceneError::Io)?)
+    }
+}
+
+impl SceneCurve {
+    pub fn curve(&self) -> Bezier {
+        let [start, middle, end] = self.points.map(cgmath::Vector2::from);
+        Bezier::new(start, middle, end)
+    }
+}
+
+impl RendererKind {
+    /// Matches `CurveRenderer::name` of the renderer it selects.
+    pub fn name(self) -> &'static str {
+        match self {
+            RendererKind::Tangent => "tangent",
+            RendererKind::Connection => "connection",
+            RendererKind::Dots => "dots",
+            RendererKind::Arrows => "arrows",
+        }
+    }
+}
+
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::Io(error) => write!(f, "could not read scene: {}", error),
+            SceneError::Json(error) => write!(f, "invalid scene: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io(error) => Some(error),
+            SceneError::Json(error) => Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::renderer::{CurveRenderer, TangentRenderer};
+
+    #[test]
+    fn straight_scene_curves_render_with_the_tangent_renderer() {
+        let scene = Scene::from_json(
+            r#"{"curves": [
+                {"points": [[-0.5, -0.5], [0.0, 0.0], [0.5, 0.5]]},
+                {"points": [[-0.5, 0.25], [0.0, 0.25], [0.5, 0.25]], "renderer": "tangent"},
+                {"points": [[0.25, -0.5], [0.25, 0.0], [0.25, 0.5]]}
+            ]}"#,
+        )
+        .unwrap();
+        for item in &scene.curves {
+            let data = TangentRenderer::new().render(&item.curve().subdivide(30), 0.01);
+            assert_eq!(data.index_count(), 29 * 6);
+            for vertex in &data.vertices {
+                let [x, y, ..]: [f32; 8] = bytemuck::cast(*vertex);
+                assert!(x.abs() <= 0.51 && y.abs() <= 0.51);
+            }
+        }
+    }
+}
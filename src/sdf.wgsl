@@ -0,0 +1,14 @@
+// Fades the stroke out over its outermost pixel. uv.x runs across the stroke
+// from 0 on one edge to 1 on the other, so its distance from the nearer edge
+// over its change per pixel is the distance to the edge in pixels. Where it
+// doesn't change, as on markers and dots, the fill stays opaque.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let per_pixel = fwidth(in.uv.x);
+    let from_edge = 0.5 - abs(in.uv.x - 0.5);
+    var alpha = 1.0;
+    if per_pixel > 0.0 {
+        alpha = clamp(from_edge / per_pixel, 0.0, 1.0);
+    }
+    return vec4<f32>(in.color, alpha);
+}
@@ -1,23 +1,23 @@
 struct VertexInput {
     @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) depth: f32,
+    @location(5) color: vec3<f32>,
 }
 
-struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec3<f32>,
+struct InstanceInput {
+    @location(3) offset: vec2<f32>,
+    @location(4) scale: f32,
 }
 
 @vertex
 fn vs_main(
-    model: VertexInput
+    model: VertexInput,
+    instance: InstanceInput,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.color = vec3<f32>(1.0, 1.0, 1.0);
-    out.clip_position = vec4<f32>(model.position, 0.0, 1.0);
+    out.color = srgb_to_linear(model.color);
+    out.clip_position = vec4<f32>(model.position * instance.scale + instance.offset, model.depth, 1.0);
+    out.uv = model.uv;
     return out;
 }
-
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color, 1.0);
-}
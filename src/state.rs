@@ -1,26 +1,111 @@
-use std::time::Duration;
+mod capture;
+mod gpu_curve;
 
-use crate::{curve::Bezier, Vertex};
+use std::{path::Path, time::Duration};
+
+use crate::{
+    config::RunConfig,
+    curve::{
+        renderer::{
+            ArrowRenderer, ConnectionRenderer, CurveRenderer, DotRenderer, TangentRenderer,
+        },
+        Animation, Bezier, PolyLine, Spline,
+    },
+    scene::{RendererKind, SceneCurve},
+    vertex::{Instance, RenderData},
+    Vertex,
+};
+use capture::FrameCapture;
+use gpu_curve::GpuCurve;
 use wgpu::ColorTargetState;
 
+const BUFFER_RING_SIZE: usize = 3;
+
 pub struct State<'window> {
     window: &'window winit::window::Window,
     surface_config: wgpu::SurfaceConfiguration,
     surface: wgpu::Surface<'window>,
     device: wgpu::Device,
     queue: wgpu::Queue,
+    depth_view: wgpu::TextureView,
+    // The multisampled color target, resolved into the surface texture.
+    msaa_view: Option<wgpu::TextureView>,
+    sample_count: u32,
 
     pipelines: [wgpu::RenderPipeline; 2],
+    strip_pipelines: [wgpu::RenderPipeline; 2],
+    title: String,
     current_pipeline: usize,
+    clear_color: wgpu::Color,
+
+    aspect_ratio: Option<f32>,
+    viewport: [f32; 4],
+
+    renderers: Vec<Box<dyn CurveRenderer>>,
+    current_renderer: usize,
 
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
+    gpu_curve: GpuCurve,
+    gpu_eval: bool,
+
+    vertex_buffers: [wgpu::Buffer; BUFFER_RING_SIZE],
+    index_buffers: [wgpu::Buffer; BUFFER_RING_SIZE],
+    buffer_slot: usize,
+    staging_belt: wgpu::util::StagingBelt,
+    upload_pending: bool,
+    instance_buffer: wgpu::Buffer,
+    time_buffer: wgpu::Buffer,
+    time_bind_group: wgpu::BindGroup,
 
     num_indices: u32,
+    // The strip follows the list in the buffers, drawn from these offsets.
+    strip_start: u32,
+    strip_base_vertex: i32,
+    num_instances: u32,
+    grid: bool,
+    strip: bool,
+
+    poly_line: PolyLine,
+    subdivision_count: usize,
+    adaptive_tessellation: bool,
+    lod_threshold_px: f32,
+    curve: Option<Bezier>,
+    fixed_curve: Option<Bezier>,
+    animation: Option<Animation>,
+    cursor: Option<cgmath::Vector2<f64>>,
+    dragging: Option<usize>,
+    snap: Option<f64>,
+    modifiers: winit::keyboard::ModifiersState,
+    undo_stack: Vec<Option<Bezier>>,
+    redo_stack: Vec<Option<Bezier>>,
+    sketch: Vec<cgmath::Vector2<f64>>,
+    sketches: Vec<PolyLine>,
+    sketch_line: PolyLine,
+    // Reused for each of the scene and `set_curves` curves in turn.
+    styled_line: PolyLine,
+    scene: Vec<SceneCurve>,
+    curves: Vec<(Bezier, f64, [f32; 3])>,
+    render_data: RenderData,
+    // Kept apart from `render_data`, which stays a triangle list.
+    strip_data: RenderData,
+    stroke_width_px: f32,
+    width: f64,
+    marker: f64,
+    // Written on its own every frame, so moving the marker doesn't redo the
+    // rest of the geometry.
+    marker_buffer: wgpu::Buffer,
+    marker_vertex_count: u32,
+    dirty: bool,
+
+    paused: bool,
+    // The index of the next frame to save, while recording.
+    recording: Option<usize>,
+    step_requested: bool,
+    animation_time: Duration,
+    last_since_start: Duration,
 }
 
 impl<'window> State<'window> {
-    pub async fn new(window: &'window winit::window::Window) -> Self {
+    pub async fn new(window: &'window winit::window::Window, mut config: RunConfig) -> Self {
         let size = window.inner_size();
 
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
@@ -39,11 +124,23 @@ impl<'window> State<'window> {
             .await
             .unwrap();
 
+        // Needed for sample counts other than 1 and 4, where the adapter has them.
+        let format_features =
+            adapter.features() & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+        // Left out where the adapter lacks it, so the fill pipelines fall
+        // back to regular rasterization.
+        let conservative_features = if config.conservative_rasterization {
+            adapter.features() & wgpu::Features::CONSERVATIVE_RASTERIZATION
+        } else {
+            wgpu::Features::empty()
+        };
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: Some("Wgpu device"),
-                    required_features: wgpu::Features::POLYGON_MODE_LINE,
+                    required_features: wgpu::Features::POLYGON_MODE_LINE
+                        | format_features
+                        | conservative_features,
                     required_limits: wgpu::Limits::default(),
                 },
                 None,
@@ -51,51 +148,218 @@ impl<'window> State<'window> {
             .await
             .unwrap();
 
-        let surface_config = Self::create_surface_config(&surface, &adapter, &size);
+        let surface_config = Self::create_surface_config(&surface, &adapter, &size, config.hdr);
         surface.configure(&device, &surface_config);
+        let sample_count = Self::supported_sample_count(
+            &adapter,
+            &device,
+            Self::color_format(&surface_config),
+            config.sample_count,
+        );
+        let depth_view = Self::create_depth_view(&device, &surface_config, sample_count);
+        let msaa_view = Self::create_msaa_view(&device, &surface_config, sample_count);
 
-        let shader_module = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
+        let fragment_source =
+            config
+                .fragment_shader
+                .as_deref()
+                .unwrap_or(if config.sdf_antialiasing {
+                    include_str!("sdf.wgsl")
+                } else {
+                    include_str!("fragment.wgsl")
+                });
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("shader.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(Self::shader_source(fragment_source).into()),
+        });
+
+        let vertex_buffers = std::array::from_fn(|_| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Vertex Buffer"),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                size: 10 * 2048,
+                mapped_at_creation: false,
+            })
+        });
+
+        let index_buffers = std::array::from_fn(|_| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Index Buffer"),
+                usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+                size: 10 * 1024,
+                mapped_at_creation: false,
+            })
+        });
 
-        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Vertex Buffer"),
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
             usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            size: 10 * 2048,
+            size: (Self::GRID_SIZE * Self::GRID_SIZE * std::mem::size_of::<Instance>())
+                as wgpu::BufferAddress,
             mapped_at_creation: false,
         });
 
-        let index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Index Buffer"),
-            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-            size: 10 * 1024,
+        let marker_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Marker Buffer"),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            size: std::mem::size_of::<[Vertex; 3]>() as wgpu::BufferAddress,
             mapped_at_creation: false,
         });
 
+        let time_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Time Buffer"),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            size: Self::TIME_BUFFER_SIZE,
+            mapped_at_creation: false,
+        });
+        let time_bind_group_layout = Self::create_time_bind_group_layout(&device);
+        let time_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Time Bind Group"),
+            layout: &time_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: time_buffer.as_entire_binding(),
+            }],
+        });
+
         // println!("{:#?} {:#?}", vertices[0], vertices[1]);
 
-        let pipelines = [
-            Self::create_fill_render_pipeline(&device, &shader_module, &surface_config),
-            Self::create_line_render_pipeline(&device, &shader_module, &surface_config),
-        ];
+        let create_pipelines = |topology| {
+            [
+                Self::create_fill_render_pipeline(
+                    &device,
+                    &shader_module,
+                    &surface_config,
+                    &time_bind_group_layout,
+                    topology,
+                    sample_count,
+                ),
+                Self::create_line_render_pipeline(
+                    &device,
+                    &shader_module,
+                    &surface_config,
+                    &time_bind_group_layout,
+                    topology,
+                    sample_count,
+                ),
+            ]
+        };
+        let pipelines = create_pipelines(wgpu::PrimitiveTopology::TriangleList);
+        let strip_pipelines = create_pipelines(wgpu::PrimitiveTopology::TriangleStrip);
+
+        let gpu_curve = GpuCurve::new(
+            &device,
+            &surface_config,
+            &time_bind_group_layout,
+            fragment_source,
+            sample_count,
+        );
 
-        Self {
+        let mut state = Self {
             window,
             surface_config,
             surface,
             device,
             queue,
+            depth_view,
+            msaa_view,
+            sample_count,
             pipelines,
+            strip_pipelines,
+            title: config.title.clone(),
             current_pipeline: 0,
-            vertex_buffer,
-            index_buffer,
+            clear_color: config.clear_color,
+            aspect_ratio: None,
+            viewport: Self::compute_viewport(None, &size),
+            renderers: [
+                Box::new(TangentRenderer::new()) as Box<dyn CurveRenderer>,
+                Box::new(ConnectionRenderer::new()),
+                Box::new(DotRenderer::new(Self::DOT_SPACING)),
+                Box::new(
+                    ArrowRenderer::new(
+                        ConnectionRenderer::new(),
+                        Self::ARROW_LENGTH,
+                        Self::ARROW_WIDTH,
+                    )
+                    .with_start(true),
+                ),
+            ]
+            .into_iter()
+            .chain(std::mem::take(&mut config.renderers))
+            .collect(),
+            current_renderer: 0,
+            gpu_curve,
+            gpu_eval: false,
+            vertex_buffers,
+            index_buffers,
+            buffer_slot: 0,
+            staging_belt: wgpu::util::StagingBelt::new(Self::STAGING_CHUNK_SIZE),
+            upload_pending: false,
+            instance_buffer,
+            time_buffer,
+            time_bind_group,
             num_indices: 0,
-        }
+            strip_start: 0,
+            strip_base_vertex: 0,
+            num_instances: 0,
+            grid: false,
+            strip: false,
+            poly_line: PolyLine::new(),
+            subdivision_count: 30,
+            adaptive_tessellation: false,
+            lod_threshold_px: config.lod_threshold_px,
+            curve: None,
+            fixed_curve: config.curve,
+            animation: config.animation.clone(),
+            cursor: None,
+            dragging: None,
+            snap: None,
+            modifiers: winit::keyboard::ModifiersState::empty(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            sketch: Vec::new(),
+            sketches: Vec::new(),
+            sketch_line: PolyLine::new(),
+            styled_line: PolyLine::new(),
+            scene: config
+                .scene
+                .as_ref()
+                .map_or_else(Vec::new, |scene| scene.curves.clone()),
+            curves: Vec::new(),
+            render_data: RenderData::new(),
+            strip_data: RenderData::new(),
+            stroke_width_px: 0.0,
+            width: 0.0,
+            marker: 0.0,
+            marker_buffer,
+            marker_vertex_count: 0,
+            dirty: true,
+            paused: false,
+            recording: None,
+            step_requested: false,
+            animation_time: Duration::ZERO,
+            last_since_start: Duration::ZERO,
+        };
+        state.set_stroke_width_px(Self::DEFAULT_STROKE_WIDTH_PX);
+        state.write_instances();
+        state.set_curves(&config.curves);
+        state.update_title();
+        state
     }
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture().unwrap();
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let output = match self.surface.get_current_texture() {
+            Ok(output) => output,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.surface.configure(&self.device, &self.surface_config);
+                self.surface.get_current_texture()?
+            }
+            Err(e) => return Err(e),
+        };
+        let view = output.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(Self::color_format(&self.surface_config)),
+            ..Default::default()
+        });
 
         let mut encoder = self
             .device
@@ -103,9 +367,23 @@ impl<'window> State<'window> {
                 label: Some("Command Encoder"),
             });
 
+        if self.upload_pending {
+            self.upload_render_data(&mut encoder);
+        }
         self.render_pass(&mut encoder, &view);
+        let capture = self
+            .recording
+            .and_then(|_| FrameCapture::new(&self.device, &output.texture, &mut encoder));
 
+        self.staging_belt.finish();
         self.queue.submit(std::iter::once(encoder.finish()));
+        self.staging_belt.recall();
+        if let Some(capture) = capture {
+            self.save_frame(capture);
+        } else if self.recording.is_some() {
+            eprintln!("cannot record frames of {:?}", self.surface_config.format);
+            self.set_recording(false);
+        }
         output.present();
 
         Ok(())
@@ -115,37 +393,86 @@ impl<'window> State<'window> {
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
-                resolve_target: None,
+                view: self.msaa_view.as_ref().unwrap_or(view),
+                resolve_target: self.msaa_view.as_ref().map(|_| view),
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
-                    store: wgpu::StoreOp::Store,
+                    load: wgpu::LoadOp::Clear(self.clear_color),
+                    store: match self.msaa_view {
+                        Some(_) => wgpu::StoreOp::Discard,
+                        None => wgpu::StoreOp::Store,
+                    },
                 },
             })],
-            depth_stencil_attachment: None,
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
             timestamp_writes: None,
             occlusion_query_set: None,
         });
 
+        let [x, y, width, height] = self.viewport;
+        render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        render_pass.set_bind_group(0, &self.time_bind_group, &[]);
         render_pass.set_pipeline(&self.pipelines[self.current_pipeline]);
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-        render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+        render_pass.set_vertex_buffer(0, self.vertex_buffers[self.buffer_slot].slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.set_index_buffer(
+            self.index_buffers[self.buffer_slot].slice(..),
+            wgpu::IndexFormat::Uint32,
+        );
+        render_pass.draw_indexed(0..self.strip_start, 0, 0..self.num_instances);
+        if self.marker_vertex_count > 0 {
+            render_pass.set_vertex_buffer(0, self.marker_buffer.slice(..));
+            render_pass.draw(0..self.marker_vertex_count, 0..self.num_instances);
+            render_pass.set_vertex_buffer(0, self.vertex_buffers[self.buffer_slot].slice(..));
+        }
+        if self.strip_start < self.num_indices {
+            render_pass.set_pipeline(&self.strip_pipelines[self.current_pipeline]);
+            render_pass.draw_indexed(
+                self.strip_start..self.num_indices,
+                self.strip_base_vertex,
+                0..self.num_instances,
+            );
+        }
+        if self.gpu_evaluates_curve() {
+            self.gpu_curve.draw(&mut render_pass, self.current_pipeline);
+        }
     }
 
     pub fn window(&self) -> &winit::window::Window {
         self.window
     }
 
-    pub fn input(&mut self, _event: &mut winit::event::WindowEvent) -> bool {
-        use winit::event::{ElementState, KeyEvent, WindowEvent};
+    const PIPELINE_NAMES: [&'static str; 2] = ["fill", "wireframe"];
+
+    fn update_title(&self) {
+        let renderer = if self.strip {
+            "connection strip"
+        } else {
+            self.renderers[self.current_renderer].name()
+        };
+        self.window.set_title(&format!(
+            "{} [{}, {}]{}",
+            self.title,
+            Self::PIPELINE_NAMES[self.current_pipeline],
+            renderer,
+            if self.recording.is_some() {
+                " recording"
+            } else {
+                ""
+            }
+        ));
+    }
+
+    pub fn input(&mut self, event: &winit::event::WindowEvent) -> bool {
+        use winit::event::{ElementState, KeyEvent, MouseButton, WindowEvent};
         use winit::keyboard::{KeyCode, PhysicalKey};
-        match _event {
+        match event {
             WindowEvent::KeyboardInput {
                 event:
                     KeyEvent {
@@ -156,12 +483,356 @@ impl<'window> State<'window> {
                 ..
             } => {
                 self.current_pipeline ^= 1;
+                self.update_title();
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyR),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.current_renderer = (self.current_renderer + 1) % self.renderers.len();
+                self.dirty = true;
+                self.update_title();
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyL),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.aspect_ratio = match self.aspect_ratio {
+                    Some(_) => None,
+                    None => Some(1.0),
+                };
+                self.viewport =
+                    Self::compute_viewport(self.aspect_ratio, &self.window.inner_size());
+                self.update_width();
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key:
+                            PhysicalKey::Code(key @ (KeyCode::ArrowUp | KeyCode::ArrowDown)),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.subdivision_count = match key {
+                    KeyCode::ArrowUp => self.subdivision_count + 1,
+                    _ => (self.subdivision_count - 1).max(2),
+                };
+                self.dirty = true;
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyA),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.adaptive_tessellation = !self.adaptive_tessellation;
+                self.dirty = true;
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(key @ (KeyCode::Equal | KeyCode::Minus)),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                let px = match key {
+                    KeyCode::Equal => self.stroke_width_px + 1.0,
+                    _ => (self.stroke_width_px - 1.0).max(1.0),
+                };
+                self.set_stroke_width_px(px);
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyP),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.paused = !self.paused;
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::Period),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.step_requested = self.paused;
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyG),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.grid = !self.grid;
+                self.write_instances();
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyT),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.strip = !self.strip;
+                self.dirty = true;
+                self.update_title();
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyC),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.set_recording(self.recording.is_none());
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyV),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                if let Err(error) = std::fs::write(Self::SVG_PATH, self.export_svg()) {
+                    eprintln!("could not write {}: {}", Self::SVG_PATH, error);
+                }
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyE),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.gpu_eval = !self.gpu_eval;
+                self.dirty = true;
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyS),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.snap = match self.snap {
+                    Some(_) => None,
+                    None => Some(Self::SNAP_GRID),
+                };
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(key @ (KeyCode::KeyZ | KeyCode::KeyY)),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } if self.modifiers.control_key() => {
+                match key {
+                    KeyCode::KeyZ => self.undo(),
+                    _ => self.redo(),
+                }
+                false
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::Enter),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            }
+            | WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Right,
+                ..
+            } => {
+                self.finish_sketch();
+                false
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let cursor = self.to_world(*position);
+                self.cursor = Some(cursor);
+                if let Some(index) = self.dragging {
+                    self.move_control_point(index, cursor);
+                }
+                false
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.dragging = self.pick_control_point();
+                if self.dragging.is_some() {
+                    self.record_edit();
+                } else if let Some(cursor) = self.cursor {
+                    self.sketch.push(self.snap_point(cursor));
+                    self.dirty = true;
+                }
+                false
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.dragging = None;
                 false
             }
             _ => false,
         }
     }
 
+    fn to_world(&self, position: winit::dpi::PhysicalPosition<f64>) -> cgmath::Vector2<f64> {
+        let [x, y, width, height] = self.viewport.map(f64::from);
+        cgmath::vec2(
+            (position.x - x) / width * 2.0 - 1.0,
+            1.0 - (position.y - y) / height * 2.0,
+        )
+    }
+
+    const PICK_RADIUS: f64 = 0.05;
+
+    fn pick_control_point(&self) -> Option<usize> {
+        use cgmath::InnerSpace;
+
+        // A scene or `set_curves` hides the animated curve, so it can't be
+        // grabbed either.
+        if !self.shows_animated_curve() {
+            return None;
+        }
+        let (curve, cursor) = (self.curve?, self.cursor?);
+        [curve.start, curve.middle, curve.end]
+            .iter()
+            .map(|point| (point - cursor).magnitude())
+            .enumerate()
+            .filter(|&(_, distance)| distance <= Self::PICK_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+
+    const SNAP_GRID: f64 = 0.1;
+
+    fn snap_point(&self, point: cgmath::Vector2<f64>) -> cgmath::Vector2<f64> {
+        match self.snap {
+            Some(grid) => point.map(|x| (x / grid).round() * grid),
+            None => point,
+        }
+    }
+
+    fn finish_sketch(&mut self) {
+        let points = std::mem::take(&mut self.sketch);
+        if points.len() >= 2 {
+            self.sketches
+                .push(Spline::catmull_rom(&points).subdivide(Self::SKETCH_SUBDIVISION_COUNT));
+        }
+        self.dirty = true;
+    }
+
+    const HISTORY_LIMIT: usize = 100;
+
+    // History only covers the animated curve while it's shown, so nothing
+    // is recorded or undone behind a scene or `set_curves`.
+    fn record_edit(&mut self) {
+        if !self.shows_animated_curve() {
+            return;
+        }
+        if self.undo_stack.len() == Self::HISTORY_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(self.fixed_curve);
+        self.redo_stack.clear();
+    }
+
+    fn undo(&mut self) {
+        if !self.shows_animated_curve() {
+            return;
+        }
+        if let Some(curve) = self.undo_stack.pop() {
+            self.redo_stack.push(self.fixed_curve);
+            self.fixed_curve = curve;
+        }
+    }
+
+    fn redo(&mut self) {
+        if !self.shows_animated_curve() {
+            return;
+        }
+        if let Some(curve) = self.redo_stack.pop() {
+            self.undo_stack.push(self.fixed_curve);
+            self.fixed_curve = curve;
+        }
+    }
+
+    fn move_control_point(&mut self, index: usize, point: cgmath::Vector2<f64>) {
+        let Some(mut curve) = self.curve else {
+            return;
+        };
+        let point = self.snap_point(point);
+        match index {
+            0 => curve.start = point,
+            1 => curve.middle = point,
+            _ => curve.end = point,
+        }
+        self.fixed_curve = Some(curve);
+    }
+
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width == 0 && new_size.height == 0 {
             return;
@@ -169,19 +840,298 @@ impl<'window> State<'window> {
         self.surface_config.height = new_size.height;
         self.surface_config.width = new_size.width;
         self.surface.configure(&self.device, &self.surface_config);
+        self.depth_view =
+            Self::create_depth_view(&self.device, &self.surface_config, self.sample_count);
+        self.msaa_view =
+            Self::create_msaa_view(&self.device, &self.surface_config, self.sample_count);
+        self.viewport = Self::compute_viewport(self.aspect_ratio, &new_size);
+        self.update_width();
+        self.dirty = true;
     }
 
-    pub fn update(&mut self, since_start: Duration) {
-        let width = 0.01;
-        let count = 30;
+    const GRID_SIZE: usize = 50;
+
+    fn write_instances(&mut self) {
+        let instances = if self.grid {
+            let scale = 1.0 / Self::GRID_SIZE as f32;
+            let cell = |i: usize| (2 * i + 1) as f32 * scale - 1.0;
+            (0..Self::GRID_SIZE * Self::GRID_SIZE)
+                .map(|i| {
+                    Instance::new(
+                        [cell(i % Self::GRID_SIZE), cell(i / Self::GRID_SIZE)],
+                        scale,
+                    )
+                })
+                .collect()
+        } else {
+            vec![Instance::identity()]
+        };
+        self.queue
+            .write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+        self.num_instances = instances.len() as u32;
+    }
+
+    const DEFAULT_STROKE_WIDTH_PX: f32 = 6.0;
+
+    const DOT_SPACING: f64 = 0.05;
+
+    const ARROW_LENGTH: f64 = 0.08;
+    const ARROW_WIDTH: f64 = 0.06;
+
+    // The larger side of the curve's bounding box in pixels.
+    fn screen_size_px(&self, curve: &Bezier) -> f32 {
+        let [_, _, viewport_width, viewport_height] = self.viewport;
+        let (min, max) = curve.bounding_box();
+        let size = max - min;
+        (size.x as f32 * viewport_width / 2.0).max(size.y as f32 * viewport_height / 2.0)
+    }
+
+    const TESSELLATION_TOLERANCE_PX: f64 = 0.25;
+
+    fn adaptive_subdivision_count(&self, curve: &Bezier) -> usize {
+        curve.subdivision_count(Self::TESSELLATION_TOLERANCE_PX / self.pixels_per_unit() as f64)
+    }
+
+    fn curve_subdivision_count(&self, curve: &Bezier) -> usize {
+        if self.adaptive_tessellation {
+            self.adaptive_subdivision_count(curve)
+        } else {
+            self.subdivision_count
+        }
+    }
+
+    pub fn set_stroke_width_px(&mut self, px: f32) {
+        self.stroke_width_px = px;
+        self.update_width();
+    }
+
+    fn update_width(&mut self) {
+        let width = self.half_width(self.stroke_width_px);
+        if self.width != width {
+            self.width = width;
+            self.dirty = true;
+        }
+    }
 
+    const RECORD_DIR: &'static str = "frames";
+
+    fn set_recording(&mut self, recording: bool) {
+        self.recording = None;
+        if recording {
+            if !self
+                .surface_config
+                .usage
+                .contains(wgpu::TextureUsages::COPY_SRC)
+            {
+                eprintln!("cannot record: the surface does not support copying from it");
+            } else if let Err(error) = std::fs::create_dir_all(Self::RECORD_DIR) {
+                eprintln!("could not create {}: {}", Self::RECORD_DIR, error);
+            } else {
+                self.recording = Some(0);
+            }
+        }
+        self.update_title();
+    }
+
+    fn save_frame(&mut self, capture: FrameCapture) {
+        let Some(index) = self.recording else {
+            return;
+        };
+        let path = Path::new(Self::RECORD_DIR).join(format!("frame_{:04}.png", index));
+        match capture.save(&self.device, &path) {
+            Ok(()) => self.recording = Some(index + 1),
+            Err(error) => {
+                eprintln!("could not write {}: {}", path.display(), error);
+                self.set_recording(false);
+            }
+        }
+    }
+
+    const SVG_PATH: &'static str = "bezier.svg";
+
+    /// The displayed curves and finished sketches as an SVG document in
+    /// window pixels, cropped to their strokes.
+    pub fn export_svg(&self) -> String {
+        use std::fmt::Write;
+
+        let [x, y, width, height] = self.viewport.map(|value| value as f64);
+        let to_pixels = |point: cgmath::Vector2<f64>| {
+            cgmath::vec2(
+                x + (point.x + 1.0) / 2.0 * width,
+                y + (1.0 - point.y) / 2.0 * height,
+            )
+        };
+        let mut curves = Vec::new();
+        if let Some(curve) = self.curve.filter(|_| self.shows_animated_curve()) {
+            curves.push((curve, self.stroke_width_px, Self::DEFAULT_COLOR));
+        }
+        for (curve, width, color, _) in self.styled_curves() {
+            curves.push((curve, width, color));
+        }
+        // Each path's data along with the bounds of its points in pixels.
+        let curve_paths = curves.into_iter().map(|(curve, width, color)| {
+            let curve = Bezier::new(
+                to_pixels(curve.start),
+                to_pixels(curve.middle),
+                to_pixels(curve.end),
+            );
+            (curve.to_svg_path(), curve.bounding_box(), width, color)
+        });
+        let sketch_paths = self.sketches.iter().filter_map(|line| {
+            let line = PolyLine::from_iter(line.points.iter().map(|&point| to_pixels(point)));
+            let first = *line.points.first()?;
+            let bounds = line
+                .points
+                .iter()
+                .fold((first, first), |(low, high), point| {
+                    (
+                        cgmath::vec2(low.x.min(point.x), low.y.min(point.y)),
+                        cgmath::vec2(high.x.max(point.x), high.y.max(point.y)),
+                    )
+                });
+            Some((
+                line.to_svg_path(),
+                bounds,
+                self.stroke_width_px,
+                Self::DEFAULT_COLOR,
+            ))
+        });
+
+        let mut paths = String::new();
+        let mut bounds: Option<(cgmath::Vector2<f64>, cgmath::Vector2<f64>)> = None;
+        for (path, (min, max), width, color) in curve_paths.chain(sketch_paths) {
+            let margin = cgmath::vec2(width as f64, width as f64) / 2.0;
+            let (min, max) = (min - margin, max + margin);
+            bounds = Some(match bounds {
+                None => (min, max),
+                Some((low, high)) => (
+                    cgmath::vec2(low.x.min(min.x), low.y.min(min.y)),
+                    cgmath::vec2(high.x.max(max.x), high.y.max(max.y)),
+                ),
+            });
+            let [r, g, b] = color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+            writeln!(
+                paths,
+                r#"  <path d="{}" fill="none" stroke="rgb({},{},{})" stroke-width="{}"/>"#,
+                path, r, g, b, width
+            )
+            .unwrap();
+        }
+
+        let (min, max) =
+            bounds.unwrap_or((cgmath::vec2(x, y), cgmath::vec2(x + width, y + height)));
+        let size = max - min;
+        let [r, g, b] = [self.clear_color.r, self.clear_color.g, self.clear_color.b]
+            .map(|channel| (linear_to_srgb(channel).clamp(0.0, 1.0) * 255.0).round() as u8);
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+            size.x, size.y, min.x, min.y, size.x, size.y
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"/>"#,
+            min.x, min.y, size.x, size.y, r, g, b
+        )
+        .unwrap();
+        svg.push_str(&paths);
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    // Half of a `px` wide stroke, in the units of the curve coordinates.
+    fn half_width(&self, px: f32) -> f64 {
+        (px / 2.0 / self.pixels_per_unit()) as f64
+    }
+
+    // The viewport spans 2 units each way, so along its shorter side this
+    // many pixels make up one.
+    fn pixels_per_unit(&self) -> f32 {
+        let [_, _, viewport_width, viewport_height] = self.viewport;
+        viewport_width.min(viewport_height).max(1.0) / 2.0
+    }
+
+    fn compute_viewport(
+        aspect_ratio: Option<f32>,
+        size: &winit::dpi::PhysicalSize<u32>,
+    ) -> [f32; 4] {
+        let (width, height) = (size.width as f32, size.height as f32);
+        match aspect_ratio {
+            Some(ratio) if width > height * ratio => {
+                let viewport_width = height * ratio;
+                [(width - viewport_width) / 2.0, 0.0, viewport_width, height]
+            }
+            Some(ratio) => {
+                let viewport_height = width / ratio;
+                [
+                    0.0,
+                    (height - viewport_height) / 2.0,
+                    width,
+                    viewport_height,
+                ]
+            }
+            None => [0.0, 0.0, width, height],
+        }
+    }
+
+    /// Replaces the curves drawn in place of the animated one after those of
+    /// the scene, each with its own stroke width in pixels and sRGB color.
+    pub fn set_curves(&mut self, items: &[(Bezier, f64, [f32; 3])]) {
+        self.curves = items.to_vec();
+        self.dirty = true;
+    }
+
+    const DEFAULT_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+
+    // The scene's curves followed by the ones from `set_curves`, with their
+    // stroke widths in pixels, colors and renderers.
+    fn styled_curves(
+        &self,
+    ) -> impl Iterator<Item = (Bezier, f32, [f32; 3], Option<RendererKind>)> + '_ {
+        let scene = self.scene.iter().map(|item| {
+            (
+                item.curve(),
+                item.width.unwrap_or(self.stroke_width_px),
+                item.color.unwrap_or(Self::DEFAULT_COLOR),
+                item.renderer,
+            )
+        });
+        let curves = self
+            .curves
+            .iter()
+            .map(|&(curve, width, color)| (curve, width as f32, color, None));
+        scene.chain(curves)
+    }
+
+    fn shows_animated_curve(&self) -> bool {
+        self.scene.is_empty() && self.curves.is_empty()
+    }
+
+    // Only the animated curve is evaluated on the GPU; everything else is
+    // still built on the CPU and drawn with it.
+    fn gpu_evaluates_curve(&self) -> bool {
+        self.gpu_eval && self.shows_animated_curve()
+    }
+
+    pub fn set_curve(&mut self, curve: Bezier) {
+        if self.curve != Some(curve) {
+            self.curve = Some(curve);
+            self.dirty = true;
+        }
+    }
+
+    const STEP: Duration = Duration::from_micros(16_667);
+
+    fn oscillation(since_start: Duration) -> Bezier {
         let speed = 1000.0;
 
         let start_y = ((since_start.as_millis() as f64) / speed).sin() * 0.5;
         let middle_y = ((since_start.as_millis() as f64) / speed * 2.0).sin();
         let end_y = ((since_start.as_millis() as f64) / speed * 1.5).sin() * 0.5;
 
-        let poly_line = Bezier::new(
+        Bezier::new(
             cgmath::Vector2 {
                 x: -0.5,
                 y: start_y,
@@ -192,37 +1142,235 @@ impl<'window> State<'window> {
             },
             cgmath::Vector2 { x: 0.5, y: end_y },
         )
-        .subdivide(count);
+    }
 
-        let renderer = crate::curve::renderer::TangentRenderer::new();
-        let data = renderer.render(&poly_line, width);
+    pub fn update(&mut self, since_start: Duration) {
+        let elapsed = since_start.saturating_sub(self.last_since_start);
+        self.last_since_start = since_start;
+        let previous_time = self.animation_time;
+        if self.recording.is_some() {
+            self.animation_time += Self::STEP;
+        } else if !self.paused {
+            self.animation_time += elapsed;
+        } else if self.step_requested {
+            self.animation_time += Self::STEP;
+            self.step_requested = false;
+        }
+        let since_start = self.animation_time;
 
+        if since_start != previous_time {
+            let time = [since_start.as_secs_f32(), 0.0, 0.0, 0.0];
+            self.queue
+                .write_buffer(&self.time_buffer, 0, bytemuck::cast_slice(&time));
+        }
+
+        let animation = self
+            .animation
+            .as_ref()
+            .filter(|animation| !animation.keyframes().is_empty());
+        let curve = match (self.fixed_curve, animation) {
+            (Some(curve), _) => curve,
+            (None, Some(animation)) => {
+                let time = since_start.as_secs_f64();
+                let duration = animation.duration();
+                animation.sample(if duration > 0.0 {
+                    time % duration
+                } else {
+                    time
+                })
+            }
+            (None, None) => Self::oscillation(since_start),
+        };
+        self.set_curve(curve);
+
+        let marker = (since_start.as_secs_f64() / Self::MARKER_PERIOD).fract();
+        if self.marker != marker || self.dirty {
+            self.marker = marker;
+            self.write_marker();
+        }
+
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+
+        let Some(curve) = self.curve else {
+            return;
+        };
+        if self.gpu_evaluates_curve() {
+            self.gpu_curve.write(&self.queue, &curve, self.width);
+        }
+        // Reused between frames so steady-state updates don't allocate.
+        let mut data = std::mem::take(&mut self.render_data);
+        data.clear();
+        let renderer = &self.renderers[self.current_renderer];
+        let visible = self.shows_animated_curve()
+            && !self.gpu_eval
+            && self.screen_size_px(&curve) >= self.lod_threshold_px;
+        if visible {
+            curve.subdivide_into(
+                self.curve_subdivision_count(&curve),
+                &mut self.poly_line.points,
+            );
+            if !self.strip {
+                renderer.render_into(&self.poly_line, self.width, &mut data);
+            }
+        }
+
+        let mut line = std::mem::take(&mut self.styled_line);
+        for (curve, width, color, kind) in self.styled_curves() {
+            if self.screen_size_px(&curve) < self.lod_threshold_px {
+                continue;
+            }
+            let renderer = kind
+                .and_then(|kind| self.renderers.iter().find(|r| r.name() == kind.name()))
+                .unwrap_or(renderer);
+            curve.subdivide_into(self.curve_subdivision_count(&curve), &mut line.points);
+            let start = data.len();
+            renderer.render_into(&line, self.half_width(width), &mut data);
+            data.color_from(start, color);
+        }
+        self.styled_line = line;
+        for line in &self.sketches {
+            renderer.render_into(line, self.width, &mut data);
+        }
+        if !self.sketch.is_empty() {
+            // Built like `Spline::subdivide`, but into the reused line.
+            self.sketch_line.points.clear();
+            for segment in Spline::catmull_rom_segments(&self.sketch) {
+                let skip = usize::from(!self.sketch_line.points.is_empty());
+                self.sketch_line.points.extend(
+                    segment
+                        .iter_subdivided(Self::SKETCH_SUBDIVISION_COUNT)
+                        .skip(skip),
+                );
+            }
+            renderer.render_into(&self.sketch_line, self.width, &mut data);
+        }
+        data.validate();
+        self.strip_data.clear();
+        if self.strip && visible {
+            ConnectionRenderer::new().render_strip_into(
+                &self.poly_line,
+                self.width,
+                &mut self.strip_data,
+            );
+        }
+
+        self.render_data = data;
+        self.upload_pending = true;
+    }
+
+    // Points per sketched segment, shared with its neighbors at the ends.
+    const SKETCH_SUBDIVISION_COUNT: usize = 16;
+
+    const STAGING_CHUNK_SIZE: wgpu::BufferAddress = 1 << 16;
+
+    // Copies go to the next slot of the ring, so they never target buffers
+    // the previous frames may still be reading from.
+    fn upload_render_data(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        self.upload_pending = false;
+        self.buffer_slot = (self.buffer_slot + 1) % BUFFER_RING_SIZE;
+        let vertex_buffer = &mut self.vertex_buffers[self.buffer_slot];
+        let index_buffer = &mut self.index_buffers[self.buffer_slot];
+        // The strip goes after the list, so it is drawn by a single call of
+        // its own with indices relative to its first vertex.
+        let vertices: [&[u8]; 2] = [
+            bytemuck::cast_slice(&self.render_data.vertices),
+            bytemuck::cast_slice(&self.strip_data.vertices),
+        ];
+        let indices: [&[u8]; 2] = [
+            bytemuck::cast_slice(&self.render_data.indices),
+            bytemuck::cast_slice(&self.strip_data.indices),
+        ];
+        let buffers = [
+            (vertex_buffer, "Vertex Buffer", vertices),
+            (index_buffer, "Index Buffer", indices),
+        ];
+        for (buffer, label, [list, strip]) in buffers {
+            Self::ensure_capacity(&self.device, buffer, label, list.len() + strip.len());
+            let Some(size) =
+                wgpu::BufferSize::new((list.len() + strip.len()) as wgpu::BufferAddress)
+            else {
+                continue;
+            };
+            let mut view = self
+                .staging_belt
+                .write_buffer(encoder, buffer, 0, size, &self.device);
+            view[..list.len()].copy_from_slice(list);
+            view[list.len()..].copy_from_slice(strip);
+        }
+        self.strip_start = self.render_data.indices.len() as u32;
+        self.strip_base_vertex = self.render_data.vertices.len() as i32;
+        self.num_indices = self.strip_start + self.strip_data.indices.len() as u32;
+    }
+
+    // Replaces `buffer` with one at least twice as large when `size` bytes
+    // no longer fit, so a growing sketch doesn't overflow it.
+    fn ensure_capacity(device: &wgpu::Device, buffer: &mut wgpu::Buffer, label: &str, size: usize) {
+        let size = size as wgpu::BufferAddress;
+        if size <= buffer.size() {
+            return;
+        }
+        *buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            usage: buffer.usage(),
+            size: size.max(buffer.size() * 2),
+            mapped_at_creation: false,
+        });
+    }
+
+    const MARKER_PERIOD: f64 = 4.0;
+
+    // Points the marker along the animated curve, when that is drawn.
+    fn write_marker(&mut self) {
+        let visible = self.curve.filter(|curve| {
+            !self.gpu_eval
+                && self.shows_animated_curve()
+                && self.screen_size_px(curve) >= self.lod_threshold_px
+        });
+        let Some(curve) = visible else {
+            self.marker_vertex_count = 0;
+            return;
+        };
+        let size = self.width * 3.0;
+        let (position, tangent, normal) = curve.frame_at(self.marker);
+        let vertices = [
+            position + tangent * size * 2.0,
+            position + normal * size,
+            position - normal * size,
+        ]
+        .map(|point| Vertex::new_f64(point.into()));
         self.queue
-            .write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&data.vertices));
-        self.queue
-            .write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(&data.indices));
-        self.num_indices = data.indices.len() as u32;
+            .write_buffer(&self.marker_buffer, 0, bytemuck::cast_slice(&vertices));
+        self.marker_vertex_count = vertices.len() as u32;
     }
 
     fn create_fill_render_pipeline(
         device: &wgpu::Device,
         shader_module: &wgpu::ShaderModule,
         surface_config: &wgpu::SurfaceConfiguration,
+        time_bind_group_layout: &wgpu::BindGroupLayout,
+        topology: wgpu::PrimitiveTopology,
+        sample_count: u32,
     ) -> wgpu::RenderPipeline {
         let vertex = Self::create_vertex_state(shader_module);
         let color_targets = Self::create_color_targets(surface_config);
         let fragment = Self::create_fragment_state(shader_module, &color_targets);
-        let primitive = Self::create_fill_primitive_state();
-        let multisample = Self::create_multisample_state();
+        let conservative = device
+            .features()
+            .contains(wgpu::Features::CONSERVATIVE_RASTERIZATION);
+        let primitive = Self::create_fill_primitive_state(topology, conservative);
+        let multisample = Self::create_multisample_state(sample_count);
 
-        let render_pipeline_layout = Self::create_pipeline_layout(device);
+        let render_pipeline_layout = Self::create_pipeline_layout(device, time_bind_group_layout);
         device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
             layout: Some(&render_pipeline_layout),
             vertex,
             fragment: Some(fragment),
             primitive,
-            depth_stencil: None,
+            depth_stencil: Some(Self::create_depth_stencil_state()),
             multisample,
             multiview: None,
         })
@@ -232,61 +1380,113 @@ impl<'window> State<'window> {
         device: &wgpu::Device,
         shader_module: &wgpu::ShaderModule,
         surface_config: &wgpu::SurfaceConfiguration,
+        time_bind_group_layout: &wgpu::BindGroupLayout,
+        topology: wgpu::PrimitiveTopology,
+        sample_count: u32,
     ) -> wgpu::RenderPipeline {
         let vertex = Self::create_vertex_state(shader_module);
         let color_targets = Self::create_color_targets(surface_config);
         let fragment = Self::create_fragment_state(shader_module, &color_targets);
-        let primitive = Self::create_line_primitive_state();
-        let multisample = Self::create_multisample_state();
+        let primitive = Self::create_line_primitive_state(topology);
+        let multisample = Self::create_multisample_state(sample_count);
 
-        let render_pipeline_layout = Self::create_pipeline_layout(device);
+        let render_pipeline_layout = Self::create_pipeline_layout(device, time_bind_group_layout);
         device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
             layout: Some(&render_pipeline_layout),
             vertex,
             fragment: Some(fragment),
             primitive,
-            depth_stencil: None,
+            depth_stencil: Some(Self::create_depth_stencil_state()),
             multisample,
             multiview: None,
         })
     }
 
-    fn create_pipeline_layout(device: &wgpu::Device) -> wgpu::PipelineLayout {
+    fn create_pipeline_layout(
+        device: &wgpu::Device,
+        time_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> wgpu::PipelineLayout {
         device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[],
+            bind_group_layouts: &[time_bind_group_layout],
             push_constant_ranges: &[],
         })
     }
 
+    // A single f32, padded to the 16 bytes some backends require of uniforms.
+    const TIME_BUFFER_SIZE: wgpu::BufferAddress = 16;
+
+    fn create_time_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Time Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    // Like the sRGB formats, the float one takes linear colors, so the clear
+    // color and shader outputs mean the same with either. It just doesn't
+    // clamp them to 1.0.
+    const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
     fn create_surface_config(
         surface: &wgpu::Surface,
         adapter: &wgpu::Adapter,
         size: &winit::dpi::PhysicalSize<u32>,
+        hdr: bool,
     ) -> wgpu::SurfaceConfiguration {
         let surface_capabilities = surface.get_capabilities(adapter);
-        let surface_format = surface_capabilities
-            .formats
+        let formats = &surface_capabilities.formats;
+        let surface_format = formats
             .iter()
-            .find(|f| f.is_srgb())
+            .find(|&&f| hdr && f == Self::HDR_FORMAT)
+            .or_else(|| formats.iter().find(|f| f.is_srgb()))
             .copied()
-            .unwrap_or(surface_capabilities.formats[0]);
+            .unwrap_or(formats[0]);
+        // Copying out of the surface is only needed for recording, so it is
+        // left out where unsupported.
+        let copy_usage = surface_capabilities.usages & wgpu::TextureUsages::COPY_SRC;
         wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | copy_usage,
             format: surface_format,
             width: size.width,
             height: size.height,
             present_mode: wgpu::PresentMode::AutoNoVsync,
             desired_maximum_frame_latency: 2,
             alpha_mode: surface_capabilities.alpha_modes[0],
-            view_formats: vec![],
+            view_formats: vec![surface_format.add_srgb_suffix()],
         }
     }
 
-    const VERTEX_BUFFERS: [wgpu::VertexBufferLayout<'static>; 1] = [Vertex::desc()];
+    // Rendering goes through an sRGB view even when the surface itself isn't
+    // sRGB, so linear shader outputs are always encoded correctly.
+    fn color_format(surface_config: &wgpu::SurfaceConfiguration) -> wgpu::TextureFormat {
+        surface_config.format.add_srgb_suffix()
+    }
+
+    fn shader_source(fragment_source: &str) -> String {
+        format!(
+            "{}\n{}\n{}\n{}",
+            include_str!("common.wgsl"),
+            include_str!("color.wgsl"),
+            include_str!("shader.wgsl"),
+            fragment_source
+        )
+    }
+
+    const VERTEX_BUFFERS: [wgpu::VertexBufferLayout<'static>; 2] =
+        [Vertex::desc(), Instance::desc()];
 
-    fn create_vertex_state(shader_module: &wgpu::ShaderModule) -> wgpu::VertexState {
+    fn create_vertex_state(shader_module: &wgpu::ShaderModule) -> wgpu::VertexState<'_> {
         wgpu::VertexState {
             module: shader_module,
             entry_point: "vs_main",
@@ -309,28 +1509,33 @@ impl<'window> State<'window> {
         surface_config: &wgpu::SurfaceConfiguration,
     ) -> Vec<Option<ColorTargetState>> {
         vec![Some(wgpu::ColorTargetState {
-            format: surface_config.format,
-            blend: Some(wgpu::BlendState::REPLACE),
+            format: Self::color_format(surface_config),
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
             write_mask: wgpu::ColorWrites::ALL,
         })]
     }
 
-    fn create_fill_primitive_state() -> wgpu::PrimitiveState {
+    // Conservative rasterization covers every pixel a triangle touches, so
+    // strokes thinner than a pixel don't drop out. It only applies to fills.
+    fn create_fill_primitive_state(
+        topology: wgpu::PrimitiveTopology,
+        conservative: bool,
+    ) -> wgpu::PrimitiveState {
         wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList,
-            strip_index_format: None,
+            topology,
+            strip_index_format: topology.is_strip().then_some(wgpu::IndexFormat::Uint32),
             front_face: wgpu::FrontFace::Ccw,
             cull_mode: None,
             polygon_mode: wgpu::PolygonMode::Fill,
             unclipped_depth: false,
-            conservative: false,
+            conservative,
         }
     }
 
-    fn create_line_primitive_state() -> wgpu::PrimitiveState {
+    fn create_line_primitive_state(topology: wgpu::PrimitiveTopology) -> wgpu::PrimitiveState {
         wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList,
-            strip_index_format: None,
+            topology,
+            strip_index_format: topology.is_strip().then_some(wgpu::IndexFormat::Uint32),
             front_face: wgpu::FrontFace::Ccw,
             cull_mode: None,
             polygon_mode: wgpu::PolygonMode::Line,
@@ -339,11 +1544,239 @@ impl<'window> State<'window> {
         }
     }
 
-    fn create_multisample_state() -> wgpu::MultisampleState {
+    const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+    fn create_depth_view(
+        device: &wgpu::Device,
+        surface_config: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
+    ) -> wgpu::TextureView {
+        device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Depth Texture"),
+                size: wgpu::Extent3d {
+                    width: surface_config.width.max(1),
+                    height: surface_config.height.max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format: Self::DEPTH_FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    fn create_msaa_view(
+        device: &wgpu::Device,
+        surface_config: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
+    ) -> Option<wgpu::TextureView> {
+        if sample_count == 1 {
+            return None;
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Multisample Texture"),
+            size: wgpu::Extent3d {
+                width: surface_config.width.max(1),
+                height: surface_config.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::color_format(surface_config),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    // The largest count up to `requested` that both the surface and the depth
+    // format support, so weaker adapters fall back instead of failing.
+    fn supported_sample_count(
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        requested: u32,
+    ) -> u32 {
+        let adapter_specific = device
+            .features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+        let flags = |format: wgpu::TextureFormat| {
+            if adapter_specific {
+                adapter.get_texture_format_features(format).flags
+            } else {
+                format.guaranteed_format_features(device.features()).flags
+            }
+        };
+        [8, 4, 2]
+            .into_iter()
+            .filter(|&count| count <= requested)
+            .find(|&count| {
+                [surface_format, Self::DEPTH_FORMAT]
+                    .into_iter()
+                    .all(|format| flags(format).sample_count_supported(count))
+            })
+            .unwrap_or(1)
+    }
+
+    fn create_depth_stencil_state() -> wgpu::DepthStencilState {
+        wgpu::DepthStencilState {
+            format: Self::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+
+    fn create_multisample_state(sample_count: u32) -> wgpu::MultisampleState {
         wgpu::MultisampleState {
-            count: 1,
+            count: sample_count,
             mask: !0,
             alpha_to_coverage_enabled: false,
         }
     }
 }
+
+fn linear_to_srgb(channel: f64) -> f64 {
+    if channel <= 0.0031308 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::util::DeviceExt;
+
+    // A vertex color picked as sRGB mid-gray has to come out of the sRGB view
+    // of the target as the same value, not the linear one re-encoded.
+    #[test]
+    fn mid_gray_survives_the_srgb_target() {
+        let instance = wgpu::Instance::default();
+        let Some(adapter) = pollster::block_on(instance.request_adapter(&Default::default()))
+        else {
+            eprintln!("skipping: no graphics adapter");
+            return;
+        };
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&Default::default(), None)).unwrap();
+
+        // Configured like a window surface. The target is created in the
+        // format its view would have, as not every backend can reinterpret.
+        let format = wgpu::TextureFormat::Rgba8Unorm;
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format,
+            width: 4,
+            height: 4,
+            present_mode: wgpu::PresentMode::AutoNoVsync,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![format.add_srgb_suffix()],
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: surface_config.width,
+                height: surface_config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: State::color_format(&surface_config),
+            usage: surface_config.usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_view = State::create_depth_view(&device, &surface_config, 1);
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(
+                State::shader_source(include_str!("fragment.wgsl")).into(),
+            ),
+        });
+        let time_bind_group_layout = State::create_time_bind_group_layout(&device);
+        let pipeline = State::create_fill_render_pipeline(
+            &device,
+            &shader_module,
+            &surface_config,
+            &time_bind_group_layout,
+            wgpu::PrimitiveTopology::TriangleList,
+            1,
+        );
+        let time_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            usage: wgpu::BufferUsages::UNIFORM,
+            size: State::TIME_BUFFER_SIZE,
+            mapped_at_creation: false,
+        });
+        let time_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &time_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: time_buffer.as_entire_binding(),
+            }],
+        });
+        // A single triangle covering the whole target.
+        let vertices = [[-1.0, -1.0], [3.0, -1.0], [-1.0, 3.0]]
+            .map(|position| Vertex::new(position).with_color([0.5; 3]));
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&[Instance::identity()]),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&pipeline);
+            render_pass.set_bind_group(0, &time_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            render_pass.draw(0..3, 0..1);
+        }
+        let capture = FrameCapture::new(&device, &texture, &mut encoder).unwrap();
+        queue.submit(std::iter::once(encoder.finish()));
+
+        for pixel in capture.read(&device).unwrap().chunks_exact(4) {
+            for &channel in &pixel[..3] {
+                assert!(channel.abs_diff(128) <= 1, "got {:?}", pixel);
+            }
+        }
+    }
+}
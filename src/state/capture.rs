@@ -0,0 +1,123 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+/// A rendered frame copied into a buffer the CPU can read, to be saved as a
+/// PNG once the copy has been submitted.
+pub struct FrameCapture {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    bgra: bool,
+}
+
+impl FrameCapture {
+    /// Records the copy of `texture` into `encoder`, or gives `None` for
+    /// formats other than 8-bit RGBA and BGRA.
+    pub fn new(
+        device: &wgpu::Device,
+        texture: &wgpu::Texture,
+        encoder: &mut wgpu::CommandEncoder,
+    ) -> Option<Self> {
+        use wgpu::TextureFormat;
+
+        let bgra = match texture.format() {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+            _ => return None,
+        };
+        let (width, height) = (texture.width(), texture.height());
+        let padded_bytes_per_row = (width * 4).div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Buffer"),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
+        Some(Self {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            bgra,
+        })
+    }
+
+    /// Waits for the submitted copy and gives its RGBA rows, top to bottom.
+    pub fn read(self, device: &wgpu::Device) -> Result<Vec<u8>, wgpu::BufferAsyncError> {
+        let slice = self.buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().map_err(|_| wgpu::BufferAsyncError)??;
+
+        let row_length = (self.width * 4) as usize;
+        let mut pixels = Vec::with_capacity(row_length * self.height as usize);
+        for row in slice
+            .get_mapped_range()
+            .chunks(self.padded_bytes_per_row as usize)
+        {
+            pixels.extend_from_slice(&row[..row_length]);
+        }
+        self.buffer.unmap();
+        if self.bgra {
+            pixels
+                .chunks_exact_mut(4)
+                .for_each(|pixel| pixel.swap(0, 2));
+        }
+        Ok(pixels)
+    }
+
+    /// Waits for the submitted copy and writes it to `path`.
+    pub fn save(self, device: &wgpu::Device, path: &Path) -> Result<(), CaptureError> {
+        let (width, height) = (self.width, self.height);
+        let pixels = self.read(device).map_err(CaptureError::Map)?;
+
+        let file = File::create(path).map_err(|error| CaptureError::Png(error.into()))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&pixels))
+            .map_err(CaptureError::Png)
+    }
+}
+
+#[derive(Debug)]
+pub enum CaptureError {
+    Map(wgpu::BufferAsyncError),
+    Png(png::EncodingError),
+}
+
+impl std::fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureError::Map(error) => write!(f, "could not read the frame back: {}", error),
+            CaptureError::Png(error) => write!(f, "could not encode the frame: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CaptureError::Map(error) => Some(error),
+            CaptureError::Png(error) => Some(error),
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use wgpu::util::DeviceExt;
+
+use super::State;
+use crate::{curve::Bezier, vertex::CurveVertex};
+
+/// Draws a stroke whose vertices are evaluated from the control points in
+/// the vertex shader, so animating the curve only rewrites a small uniform.
+pub(super) struct GpuCurve {
+    pipelines: [wgpu::RenderPipeline; 2],
+    curve_buffer: wgpu::Buffer,
+    curve_bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    num_indices: u32,
+}
+
+impl GpuCurve {
+    const SAMPLES: u32 = 128;
+
+    // Start, middle and end followed by the half-width, padded to 32 bytes.
+    const CURVE_BUFFER_SIZE: wgpu::BufferAddress = 32;
+
+    pub fn new(
+        device: &wgpu::Device,
+        surface_config: &wgpu::SurfaceConfiguration,
+        time_bind_group_layout: &wgpu::BindGroupLayout,
+        fragment_source: &str,
+        sample_count: u32,
+    ) -> Self {
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("gpu_curve.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!(
+                    "{}\n{}\n{}\n{}",
+                    include_str!("../common.wgsl"),
+                    include_str!("../color.wgsl"),
+                    include_str!("../gpu_curve.wgsl"),
+                    fragment_source
+                )
+                .into(),
+            ),
+        });
+
+        let curve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Curve Buffer"),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            size: Self::CURVE_BUFFER_SIZE,
+            mapped_at_creation: false,
+        });
+        let curve_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Curve Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let curve_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Curve Bind Group"),
+            layout: &curve_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: curve_buffer.as_entire_binding(),
+            }],
+        });
+
+        let vertices: Vec<_> = (0..Self::SAMPLES)
+            .flat_map(|i| {
+                let t = i as f32 / (Self::SAMPLES - 1) as f32;
+                [CurveVertex::new(t, -1.0), CurveVertex::new(t, 1.0)]
+            })
+            .collect();
+        let indices: Vec<u32> = (0..Self::SAMPLES - 1)
+            .flat_map(|i| {
+                let j = i * 2;
+                [j, j + 2, j + 3, j, j + 3, j + 1]
+            })
+            .collect();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Curve Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Curve Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Curve Pipeline Layout"),
+            bind_group_layouts: &[time_bind_group_layout, &curve_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let conservative = device
+            .features()
+            .contains(wgpu::Features::CONSERVATIVE_RASTERIZATION);
+        let pipelines = [
+            State::create_fill_primitive_state(wgpu::PrimitiveTopology::TriangleList, conservative),
+            State::create_line_primitive_state(wgpu::PrimitiveTopology::TriangleList),
+        ]
+        .map(|primitive| {
+            Self::create_render_pipeline(
+                device,
+                &shader_module,
+                surface_config,
+                &layout,
+                primitive,
+                sample_count,
+            )
+        });
+
+        Self {
+            pipelines,
+            curve_buffer,
+            curve_bind_group,
+            vertex_buffer,
+            index_buffer,
+            num_indices: indices.len() as u32,
+        }
+    }
+
+    pub fn write(&self, queue: &wgpu::Queue, curve: &Bezier, width: f64) {
+        let data = [curve.start, curve.middle, curve.end]
+            .iter()
+            .flat_map(|point| [point.x as f32, point.y as f32])
+            .chain([width as f32, 0.0])
+            .collect::<Vec<_>>();
+        queue.write_buffer(&self.curve_buffer, 0, bytemuck::cast_slice(&data));
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, pipeline: usize) {
+        render_pass.set_pipeline(&self.pipelines[pipeline]);
+        render_pass.set_bind_group(1, &self.curve_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+    }
+
+    const VERTEX_BUFFERS: [wgpu::VertexBufferLayout<'static>; 1] = [CurveVertex::desc()];
+
+    fn create_render_pipeline(
+        device: &wgpu::Device,
+        shader_module: &wgpu::ShaderModule,
+        surface_config: &wgpu::SurfaceConfiguration,
+        layout: &wgpu::PipelineLayout,
+        primitive: wgpu::PrimitiveState,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        let color_targets = State::create_color_targets(surface_config);
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Curve Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader_module,
+                entry_point: "vs_main",
+                buffers: &Self::VERTEX_BUFFERS,
+            },
+            fragment: Some(State::create_fragment_state(shader_module, &color_targets)),
+            primitive,
+            depth_stencil: Some(State::create_depth_stencil_state()),
+            multisample: State::create_multisample_state(sample_count),
+            multiview: None,
+        })
+    }
+}
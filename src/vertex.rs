@@ -2,21 +2,100 @@
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     position: [f32; 2],
+    uv: [f32; 2],
+    depth: f32,
+    color: [f32; 3],
 }
 
 impl Vertex {
     pub fn new(position: [f32; 2]) -> Vertex {
-        Vertex { position }
+        Vertex {
+            position,
+            uv: [0.0, 0.0],
+            depth: 0.0,
+            color: [1.0, 1.0, 1.0],
+        }
     }
 
     pub fn new_f64(position: [f64; 2]) -> Vertex {
-        Vertex {
-            position: position.map(|x| x as f32),
+        Vertex::new(position.map(|x| x as f32))
+    }
+
+    pub fn with_uv(self, uv: [f32; 2]) -> Vertex {
+        Vertex { uv, ..self }
+    }
+
+    /// Depth in [0, 1]; smaller values are drawn on top, ties go to whatever
+    /// is drawn last.
+    pub fn with_depth(self, depth: f32) -> Vertex {
+        Vertex { depth, ..self }
+    }
+
+    /// sRGB color from 0 to 1, white by default.
+    pub fn with_color(self, color: [f32; 3]) -> Vertex {
+        Vertex { color, ..self }
+    }
+
+    // Locations 3 and 4 belong to `Instance`.
+    const ATTRIBS: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+        0 => Float32x2, 1 => Float32x2, 2 => Float32, 5 => Float32x3
+    ];
+
+    pub const fn desc() -> wgpu::VertexBufferLayout<'static> {
+        use std::mem;
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Instance {
+    offset: [f32; 2],
+    scale: f32,
+}
+
+impl Instance {
+    pub fn new(offset: [f32; 2], scale: f32) -> Instance {
+        Instance { offset, scale }
+    }
+
+    pub fn identity() -> Instance {
+        Instance::new([0.0, 0.0], 1.0)
+    }
+
+    const ATTRIBS: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![3 => Float32x2, 4 => Float32];
+
+    pub const fn desc() -> wgpu::VertexBufferLayout<'static> {
+        use std::mem;
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBS,
         }
     }
+}
+
+/// A sample of a curve evaluated in the vertex shader: the parameter and the
+/// side of the stroke (-1 or 1).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CurveVertex {
+    t: f32,
+    side: f32,
+}
+
+impl CurveVertex {
+    pub fn new(t: f32, side: f32) -> CurveVertex {
+        CurveVertex { t, side }
+    }
 
     const ATTRIBS: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x3];
+        wgpu::vertex_attr_array![0 => Float32, 1 => Float32];
 
     pub const fn desc() -> wgpu::VertexBufferLayout<'static> {
         use std::mem;
@@ -28,6 +107,34 @@ impl Vertex {
     }
 }
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vertex3 {
+    position: [f32; 3],
+}
+
+impl Vertex3 {
+    pub fn new(position: [f32; 3]) -> Vertex3 {
+        Vertex3 { position }
+    }
+
+    pub fn new_f64(position: [f64; 3]) -> Vertex3 {
+        Vertex3::new(position.map(|x| x as f32))
+    }
+
+    const ATTRIBS: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x3];
+
+    pub const fn desc() -> wgpu::VertexBufferLayout<'static> {
+        use std::mem;
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct RenderData {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
@@ -41,15 +148,204 @@ impl RenderData {
         }
     }
 
-    pub fn merge(self: RenderData, other: RenderData) -> RenderData {
-        let vertices_len = self.vertices.len() as u32;
-        RenderData {
-            vertices: self.vertices.into_iter().chain(other.vertices).collect(),
-            indices: self
-                .indices
-                .into_iter()
-                .chain(other.indices.into_iter().map(|i| i + vertices_len))
-                .collect(),
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Same as `len`, named to pair with `index_count`.
+    pub fn vertex_count(&self) -> usize {
+        self.len()
+    }
+
+    pub fn index_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Whether the data fits into buffers holding `max_vertices` vertices and
+    /// `max_indices` indices.
+    pub fn fits_within(&self, max_vertices: usize, max_indices: usize) -> bool {
+        self.vertex_count() <= max_vertices && self.index_count() <= max_indices
+    }
+
+    pub fn bounding_box(&self) -> Option<(cgmath::Vector2<f64>, cgmath::Vector2<f64>)> {
+        let positions = self
+            .vertices
+            .iter()
+            .map(|vertex| cgmath::vec2(vertex.position[0] as f64, vertex.position[1] as f64));
+        positions.fold(None, |bounds, position| match bounds {
+            None => Some((position, position)),
+            Some((min, max)) => Some((
+                cgmath::vec2(min.x.min(position.x), min.y.min(position.y)),
+                cgmath::vec2(max.x.max(position.x), max.y.max(position.y)),
+            )),
+        })
+    }
+
+    pub fn validate(&self) {
+        debug_assert!(
+            self.indices.iter().all(
+                |&index| index == Self::STRIP_RESTART || (index as usize) < self.vertices.len()
+            ),
+            "index out of range of {} vertices",
+            self.vertices.len()
+        );
+    }
+
+    /// Writes a triangle list as a Wavefront OBJ mesh; strip data isn't one.
+    pub fn to_obj(&self) -> String {
+        use std::fmt::Write;
+
+        self.debug_assert_list();
+        let mut obj = String::new();
+        for vertex in &self.vertices {
+            let [x, y] = vertex.position;
+            writeln!(obj, "v {} {} 0", x, y).unwrap();
         }
+        for triangle in self.indices.chunks_exact(3) {
+            writeln!(
+                obj,
+                "f {} {} {}",
+                triangle[0] + 1,
+                triangle[1] + 1,
+                triangle[2] + 1
+            )
+            .unwrap();
+        }
+        obj
+    }
+
+    pub fn merge(mut self: RenderData, other: RenderData) -> RenderData {
+        self.append(&other);
+        self
+    }
+
+    pub fn append(&mut self, other: &RenderData) {
+        self.extend(
+            other.vertices.iter().copied(),
+            other.indices.iter().copied(),
+        );
+    }
+
+    /// Index that ends a triangle strip, so the next one starts anew. It is
+    /// kept as is when offsetting indices. Data holding strips can only be
+    /// drawn with a strip topology; the methods reading triangles expect a
+    /// list without it.
+    pub const STRIP_RESTART: u32 = u32::MAX;
+
+    /// Appends vertices along with indices relative to the first of them.
+    pub fn extend(
+        &mut self,
+        vertices: impl IntoIterator<Item = Vertex>,
+        indices: impl IntoIterator<Item = u32>,
+    ) {
+        let offset = self.vertices.len() as u32;
+        self.vertices.extend(vertices);
+        self.indices.extend(indices.into_iter().map(|i| match i {
+            Self::STRIP_RESTART => i,
+            _ => i + offset,
+        }));
+    }
+
+    /// The triangles of a triangle list; strip data isn't one.
+    pub fn triangles(&self) -> impl Iterator<Item = [Vertex; 3]> + '_ {
+        self.debug_assert_list();
+        self.indices.chunks_exact(3).map(|triangle| {
+            [
+                self.vertices[triangle[0] as usize],
+                self.vertices[triangle[1] as usize],
+                self.vertices[triangle[2] as usize],
+            ]
+        })
+    }
+
+    /// Reorders the indices of every triangle with clockwise vertices so all
+    /// of them are counter-clockwise, which is front facing with
+    /// `FrontFace::Ccw`. Degenerate triangles are left as they are. Expects a
+    /// triangle list.
+    pub fn normalize_winding(&mut self) {
+        self.debug_assert_list();
+        for triangle in self.indices.chunks_exact_mut(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]]
+                .map(|index| self.vertices[index as usize].position);
+            let doubled_area = (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]);
+            if doubled_area < 0.0 {
+                triangle.swap(1, 2);
+            }
+        }
+    }
+
+    fn debug_assert_list(&self) {
+        debug_assert!(
+            !self.indices.contains(&Self::STRIP_RESTART),
+            "expected a triangle list, not triangle strips"
+        );
+    }
+
+    /// Colors the vertices from `start` on, such as the ones appended since
+    /// `len` returned `start`.
+    pub fn color_from(&mut self, start: usize, color: [f32; 3]) {
+        for vertex in &mut self.vertices[start..] {
+            vertex.color = color;
+        }
+    }
+
+    /// Empties the data but keeps the allocations for reuse.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+    }
+}
+
+impl Default for RenderData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::renderer::{ConnectionRenderer, CurveRenderer};
+    use crate::curve::Bezier;
+
+    fn doubled_area([a, b, c]: [Vertex; 3]) -> f32 {
+        let [a, b, c] = [a.position, b.position, c.position];
+        (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])
+    }
+
+    #[test]
+    fn normalize_winding_makes_triangles_ccw() {
+        let curve = Bezier::new(
+            cgmath::vec2(0.5, 0.0),
+            cgmath::vec2(0.0, 1.0),
+            cgmath::vec2(-0.5, -0.3),
+        );
+        let mut data = ConnectionRenderer::new().render(&curve.subdivide(20), 0.05);
+        assert!(data
+            .triangles()
+            .any(|triangle| doubled_area(triangle) < 0.0));
+
+        data.normalize_winding();
+        assert!(data
+            .triangles()
+            .all(|triangle| doubled_area(triangle) >= 0.0));
+    }
+
+    #[test]
+    fn to_obj_has_a_line_per_vertex_and_triangle() {
+        let mut data = RenderData::new();
+        data.extend(
+            [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]].map(Vertex::new),
+            [0, 1, 2, 0, 2, 3],
+        );
+        let obj = data.to_obj();
+        let lines = |prefix| obj.lines().filter(move |line| line.starts_with(prefix));
+        assert_eq!(lines("v ").count(), data.vertex_count());
+        assert_eq!(lines("f ").count(), data.index_count() / 3);
+        assert_eq!(lines("f ").collect::<Vec<_>>(), ["f 1 2 3", "f 1 3 4"]);
     }
 }